@@ -0,0 +1,245 @@
+#![allow(clippy::needless_return)]
+
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use clap::{Parser, ValueEnum};
+use cp_hierarchical_clustering::*;
+
+/// Hierarchical clustering of csv, tsv or parquet feature tables
+#[derive(Parser)]
+#[command(name = "cp-cluster", version, about)]
+struct Args {
+    /// Input files, concatenated in order (they must share a schema)
+    #[arg(required = true)]
+    inputs: Vec<PathBuf>,
+
+    /// Input format, `auto` picks it from the file extension: `.parquet`/`.pq` as parquet,
+    /// `.tsv`/`.tab`/`.txt` as tab separated and anything else as csv
+    #[arg(long, value_enum, default_value_t = InputFormat::Auto)]
+    format: InputFormat,
+
+    /// Distance metric: pearson or distance
+    #[arg(long, default_value = "pearson")]
+    metric: Metric,
+
+    /// Linkage method: single, complete or average
+    #[arg(long, default_value = "average")]
+    linkage: LinkageMethod,
+
+    /// Feature column to use (repeatable), every numeric column when no include is given
+    #[arg(long, value_name = "NAME")]
+    include: Vec<String>,
+
+    /// Regex on the column names of feature columns to use (repeatable)
+    #[arg(long, value_name = "REGEX")]
+    include_regex: Vec<String>,
+
+    /// Column to leave out (repeatable)
+    #[arg(long, value_name = "NAME")]
+    exclude: Vec<String>,
+
+    /// Regex on the column names of columns to leave out (repeatable), e.g. `^Metadata_`
+    #[arg(long, value_name = "REGEX")]
+    exclude_regex: Vec<String>,
+
+    /// Column holding the row labels
+    #[arg(long, value_name = "NAME")]
+    label_col: Option<String>,
+
+    /// Cluster the rows, the feature columns or both
+    #[arg(long, value_enum, default_value_t = Axis::Rows)]
+    axis: Axis,
+
+    /// Output path of the json tree, written compactly unless `--pretty` is given
+    #[arg(long, value_name = "PATH")]
+    tree: Option<String>,
+
+    /// Pretty print the json tree, its size grows quadratically with the depth of the tree
+    #[arg(long, requires = "tree")]
+    pretty: bool,
+
+    /// Output path of the json merge list
+    #[arg(long, value_name = "PATH")]
+    merges: Option<String>,
+
+    /// Output path of the leaf order csv
+    #[arg(long, value_name = "PATH")]
+    leaf_order: Option<String>,
+
+    /// Output path of the flat cluster csv, needs `-k` or `--height`
+    #[arg(long, value_name = "PATH")]
+    clusters: Option<String>,
+
+    /// Output path of an svg dendrogram
+    #[arg(long, value_name = "PATH")]
+    svg: Option<String>,
+
+    /// Number of flat clusters
+    #[arg(short, long, conflicts_with = "height")]
+    k: Option<usize>,
+
+    /// Height at which the tree is cut into flat clusters
+    #[arg(long)]
+    height: Option<f64>,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum InputFormat {
+    Auto,
+    Csv,
+    Tsv,
+    Parquet,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Axis {
+    Rows,
+    Columns,
+    Both,
+}
+
+fn main() {
+    let args = Args::parse();
+    if let Err(e) = run(&args) {
+        eprintln!("cp-cluster: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(args: &Args) -> Result<(), Box<dyn Error>> {
+    if args.tree.is_none()
+        && args.merges.is_none()
+        && args.leaf_order.is_none()
+        && args.clusters.is_none()
+        && args.svg.is_none()
+    {
+        return Err(
+            "No output requested, pass --tree, --merges, --leaf-order, --clusters or --svg".into(),
+        );
+    }
+    if args.clusters.is_some() && args.k.is_none() && args.height.is_none() {
+        return Err("--clusters needs either -k or --height".into());
+    }
+
+    let format = match args.format {
+        InputFormat::Auto => detect_format(&args.inputs[0]),
+        format => format,
+    };
+    let lf = match format {
+        InputFormat::Parquet => scan_parquet_files(&args.inputs)?,
+        InputFormat::Tsv => scan_csv_files(&args.inputs, b'\t')?,
+        _ => scan_csv_files(&args.inputs, b',')?,
+    };
+
+    let features = lazy_to_matrix(lf, &column_spec(args), args.label_col.as_deref())?;
+
+    if args.axis != Axis::Columns {
+        let suffix = if args.axis == Axis::Both { "_rows" } else { "" };
+        let hierarchy = cluster(args, &features.data, features.labels.clone())?;
+        write_outputs(args, &hierarchy, suffix)?;
+    }
+    if args.axis != Axis::Rows {
+        let suffix = if args.axis == Axis::Both { "_cols" } else { "" };
+        let hierarchy = cluster(
+            args,
+            &transpose(&features.data),
+            Some(features.feature_names.clone()),
+        )?;
+        write_outputs(args, &hierarchy, suffix)?;
+    }
+
+    return Ok(());
+}
+
+fn detect_format(path: &Path) -> InputFormat {
+    let ext = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase());
+
+    match ext.as_deref() {
+        Some("parquet") | Some("pq") => return InputFormat::Parquet,
+        Some("tsv") | Some("tab") | Some("txt") => return InputFormat::Tsv,
+        _ => return InputFormat::Csv,
+    }
+}
+
+fn column_spec(args: &Args) -> ColumnSpec {
+    let selectors = |names: &[String], patterns: &[String]| -> Vec<ColumnSelector> {
+        return names
+            .iter()
+            .map(|name| ColumnSelector::Name(name.clone()))
+            .chain(
+                patterns
+                    .iter()
+                    .map(|pat| ColumnSelector::Regex(pat.clone())),
+            )
+            .collect();
+    };
+
+    return ColumnSpec {
+        include: selectors(&args.include, &args.include_regex),
+        exclude: selectors(&args.exclude, &args.exclude_regex),
+    };
+}
+
+fn cluster(
+    args: &Args,
+    data: &[Vec<f64>],
+    labels: Option<Vec<String>>,
+) -> Result<ClusterHierarchy, Box<dyn Error>> {
+    match labels {
+        Some(labels) => return create_labeled_hierarchy(data, args.metric, args.linkage, labels),
+        None => return create_hierarchy(data, args.metric, args.linkage),
+    }
+}
+
+fn write_outputs(
+    args: &Args,
+    hierarchy: &ClusterHierarchy,
+    suffix: &str,
+) -> Result<(), Box<dyn Error>> {
+    if let Some(path) = &args.tree {
+        match args.pretty {
+            true => hierarchy.write_tree(&with_suffix(path, suffix))?,
+            false => hierarchy.write_compact_tree(&with_suffix(path, suffix))?,
+        }
+    }
+    if let Some(path) = &args.merges {
+        hierarchy.simple_save(&with_suffix(path, suffix))?;
+    }
+    if let Some(path) = &args.leaf_order {
+        hierarchy.write_leaf_order(&with_suffix(path, suffix))?;
+    }
+    if let Some(path) = &args.clusters {
+        let clusters = match (args.k, args.height) {
+            (Some(k), _) => hierarchy.flat_clusters(k)?,
+            (None, Some(height)) => hierarchy.flat_clusters_at_height(height),
+            (None, None) => unreachable!(),
+        };
+        hierarchy.write_flat_clusters(&with_suffix(path, suffix), &clusters)?;
+    }
+    if let Some(path) = &args.svg {
+        hierarchy.write_dendrogram_svg(&with_suffix(path, suffix), &DendrogramStyle::default())?;
+    }
+
+    return Ok(());
+}
+
+/// Inserts `suffix` before the extension, `out/tree.json` -> `out/tree_rows.json`
+fn with_suffix(path: &str, suffix: &str) -> String {
+    if suffix.is_empty() {
+        return path.to_string();
+    }
+
+    let path = Path::new(path);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let fname = match path.extension() {
+        Some(ext) => format!("{}{}.{}", stem, suffix, ext.to_string_lossy()),
+        None => format!("{}{}", stem, suffix),
+    };
+
+    return path.with_file_name(fname).to_string_lossy().to_string();
+}
@@ -4,16 +4,55 @@ use super::{
     linkages::{calc_dist, LinkageMethod},
     ClusterHierarchy, ClusterMap, Distances,
 };
+use ndarray::ArrayView2;
 use rayon::prelude::*;
 
+/// Agglomerative clustering of a symmetric distance matrix, only its upper triangle is read
 pub fn hierarchical_clustering(
     dist_mat: &[Vec<f64>],
     linkage: LinkageMethod,
 ) -> Result<ClusterHierarchy, Box<dyn Error>> {
-    let dist = Distances::new(dist_mat);
+    let n = dist_mat.len();
+    if let Some((i, row)) = dist_mat.iter().enumerate().find(|(_, row)| row.len() != n) {
+        return Err(format!(
+            "Distance matrix must be square but row {} has {} values for {} rows",
+            i,
+            row.len(),
+            n
+        )
+        .into());
+    }
+
+    return cluster_distances(Distances::new(dist_mat), linkage);
+}
+
+/// Same as `hierarchical_clustering` but takes the distance matrix as an ndarray view
+///
+/// The upper triangle is copied straight out of the view, without an intermediate `Vec`.
+pub fn hierarchical_clustering_from_array(
+    dist_mat: ArrayView2<f64>,
+    linkage: LinkageMethod,
+) -> Result<ClusterHierarchy, Box<dyn Error>> {
+    if dist_mat.nrows() != dist_mat.ncols() {
+        return Err(format!(
+            "Distance matrix must be square but is {}x{}",
+            dist_mat.nrows(),
+            dist_mat.ncols()
+        )
+        .into());
+    }
+
+    return cluster_distances(Distances::from_array(dist_mat), linkage);
+}
+
+/// Agglomerative clustering over the condensed distances
+fn cluster_distances(
+    dist: Distances,
+    linkage: LinkageMethod,
+) -> Result<ClusterHierarchy, Box<dyn Error>> {
     let n = dist.len();
     if n == 0 {
-        return Ok(ClusterHierarchy::new(&vec![(0, 0, 0.0, 0)], 0));
+        return Ok(ClusterHierarchy::new(&[(0, 0, 0.0, 0)], 0));
     }
 
     let mut cluster_map = ClusterMap::new(n);
@@ -57,8 +96,8 @@ pub fn hierarchical_clustering(
         next_cid += 1;
 
         let mut merged_cids = Vec::new();
-        merged_cids.extend_from_slice(&cluster_map.get_cluster(cid1));
-        merged_cids.extend_from_slice(&cluster_map.get_cluster(cid2));
+        merged_cids.extend_from_slice(cluster_map.get_cluster(cid1));
+        merged_cids.extend_from_slice(cluster_map.get_cluster(cid2));
 
         if new_cid >= cluster_map.len() {
             cluster_map.add_new_cid(new_cid + 1);
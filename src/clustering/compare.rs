@@ -0,0 +1,287 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+};
+
+use super::ClusterHierarchy;
+use crate::matrix_op::metrics::pearson_r;
+
+/// # Baker's gamma between two hierarchies over the same leaves
+///
+/// For every pair of leaves takes the number of clusters `k` at which the pair is first put in
+/// the same cluster, and returns the Spearman correlation of these values between the two
+/// trees (as `cor_bakers_gamma` in R `dendextend`). 1 means identical nesting, values around 0
+/// unrelated trees. Uses O(n²) memory.
+///
+/// Leaves are matched by label when both hierarchies are labelled and by leaf id otherwise.
+/// Returns an error for fewer than 3 leaves, where the correlation is undefined.
+pub fn bakers_gamma(
+    first: &ClusterHierarchy,
+    second: &ClusterHierarchy,
+) -> Result<f64, Box<dyn Error>> {
+    let ids = matched_leaves(first, second)?;
+
+    // pairs joined by merge `t` are first together at k = n - 1 - t, ranking by `t` is the same
+    let first_k = pair_values(first, None, |t, _| t as f64);
+    let second_k = pair_values(second, Some(&ids), |t, _| t as f64);
+
+    return correlation(&ranks(&first_k), &ranks(&second_k));
+}
+
+/// # Cophenetic correlation between two hierarchies over the same leaves
+///
+/// Pearson correlation of the cophenetic distances (the merge height at which every pair of
+/// leaves is joined) of the two trees. Uses O(n²) memory. Leaves are matched like in
+/// `bakers_gamma`. Returns an error when the correlation is undefined: fewer than 3 leaves or
+/// a tree with all merges at the same height.
+pub fn cophenetic_correlation(
+    first: &ClusterHierarchy,
+    second: &ClusterHierarchy,
+) -> Result<f64, Box<dyn Error>> {
+    let ids = matched_leaves(first, second)?;
+
+    let first_dists = pair_values(first, None, |_, height| height);
+    let second_dists = pair_values(second, Some(&ids), |_, height| height);
+
+    return correlation(&first_dists, &second_dists);
+}
+
+/// # Robinson–Foulds distance between two hierarchies over the same leaves
+///
+/// Number of clusters (leaf sets of the internal nodes below the root) found in only one of
+/// the trees, between 0 for the same topology and `2 (n - 2)`. Runs in O(n): the clusters of
+/// the first tree are contiguous ranges of its leaf order, so a cluster of the second tree is
+/// shared when its leaves cover exactly one of those ranges. Leaves are matched like in
+/// `bakers_gamma`.
+pub fn robinson_foulds(
+    first: &ClusterHierarchy,
+    second: &ClusterHierarchy,
+) -> Result<usize, Box<dyn Error>> {
+    let ids = matched_leaves(first, second)?;
+    let n = first.original_n;
+
+    let view = first.tree_view();
+    let mut position = vec![0; n];
+    for (pos, &leaf) in view.leaf_order().iter().enumerate() {
+        position[leaf] = pos;
+    }
+    let first_ranges: HashSet<(usize, usize)> = first
+        .merges
+        .iter()
+        .map(|merge| {
+            let leaves = view.leaves(merge.new_cid);
+            (position[leaves[0]], position[leaves[leaves.len() - 1]])
+        })
+        .collect();
+
+    // (first position, last position) in the first tree's leaf order of every second tree node
+    let mut second_to_first = vec![0; n];
+    for (leaf, &id) in ids.iter().enumerate() {
+        second_to_first[id] = leaf;
+    }
+    let max_cid = second.merges.iter().map(|m| m.new_cid).max().unwrap_or(0);
+    let mut ranges: Vec<(usize, usize)> = vec![(0, 0); (max_cid + 1).max(n)];
+    for (leaf, range) in ranges.iter_mut().enumerate().take(n) {
+        let pos = position[second_to_first[leaf]];
+        *range = (pos, pos);
+    }
+
+    let mut shared: usize = 0;
+    for merge in &second.merges {
+        let (lo1, hi1) = ranges[merge.cid1];
+        let (lo2, hi2) = ranges[merge.cid2];
+        let range = (lo1.min(lo2), hi1.max(hi2));
+        ranges[merge.new_cid] = range;
+
+        if range.1 - range.0 + 1 == merge.size && first_ranges.contains(&range) {
+            shared += 1;
+        }
+    }
+
+    // the roots hold every leaf and are always shared, they don't count as clusters
+    let shared = shared.saturating_sub(1);
+    return Ok(2 * (n.saturating_sub(2) - shared));
+}
+
+/// # Fowlkes–Mallows index of the flat clusterings of two hierarchies cut into `k` clusters
+///
+/// `B_k = T_k / sqrt(P_k Q_k)` from the contingency table of the two cuts, 1 when both trees
+/// give the same `k` clusters. Defined for `1 <= k < n`. Leaves are matched like in
+/// `bakers_gamma`.
+pub fn fowlkes_mallows(
+    first: &ClusterHierarchy,
+    second: &ClusterHierarchy,
+    k: usize,
+) -> Result<f64, Box<dyn Error>> {
+    let ids = matched_leaves(first, second)?;
+    if k == 0 || k >= first.original_n {
+        return Err(format!(
+            "k must be between 1 and {} but got {}",
+            first.original_n.saturating_sub(1),
+            k
+        )
+        .into());
+    }
+
+    return Ok(fowlkes_mallows_index(
+        &first.flat_clusters(k)?,
+        &second.flat_clusters(k)?,
+        &ids,
+    ));
+}
+
+/// # Fowlkes–Mallows index for every `k` from 2 to `n - 1`
+///
+/// Returns `(k, B_k)` pairs, the curve of `fowlkes_mallows` over all cuts. Takes O(n²).
+pub fn fowlkes_mallows_curve(
+    first: &ClusterHierarchy,
+    second: &ClusterHierarchy,
+) -> Result<Vec<(usize, f64)>, Box<dyn Error>> {
+    let ids = matched_leaves(first, second)?;
+
+    let mut curve: Vec<(usize, f64)> = Vec::new();
+    for k in 2..first.original_n {
+        let index =
+            fowlkes_mallows_index(&first.flat_clusters(k)?, &second.flat_clusters(k)?, &ids);
+        curve.push((k, index));
+    }
+
+    return Ok(curve);
+}
+
+/// # Matches the leaves of two hierarchies
+///
+/// Returns the leaf id in `second` of every leaf of `first`. Leaves are matched by label when
+/// both hierarchies are labelled (the labels must be unique and the same sets) and by id
+/// otherwise.
+pub(crate) fn matched_leaves(
+    first: &ClusterHierarchy,
+    second: &ClusterHierarchy,
+) -> Result<Vec<usize>, Box<dyn Error>> {
+    let n = first.original_n;
+    if second.original_n != n {
+        return Err(format!("Hierarchies have {} and {} leaves", n, second.original_n).into());
+    }
+
+    let (first_labels, second_labels) = match (first.labels(), second.labels()) {
+        (Some(first_labels), Some(second_labels)) => (first_labels, second_labels),
+        _ => return Ok((0..n).collect()),
+    };
+
+    let mut second_ids: HashMap<&str, usize> = HashMap::with_capacity(n);
+    for (id, label) in second_labels.iter().enumerate() {
+        if second_ids.insert(label, id).is_some() {
+            return Err(format!("Duplicate leaf label '{}'", label).into());
+        }
+    }
+
+    let mut ids = Vec::with_capacity(n);
+    let mut seen = vec![false; n];
+    for label in first_labels {
+        let id = *second_ids.get(label.as_str()).ok_or(format!(
+            "Leaf '{}' is missing from the second hierarchy",
+            label
+        ))?;
+        if seen[id] {
+            return Err(format!("Duplicate leaf label '{}'", label).into());
+        }
+        seen[id] = true;
+        ids.push(id);
+    }
+
+    return Ok(ids);
+}
+
+/// # Value of every leaf pair in condensed order
+///
+/// Pairs joined by merge `t` at height `h` get `value(t, h)`. For the second hierarchy `ids`
+/// (from `matched_leaves`) maps its leaves back to the leaf ids of the first one, so both trees
+/// fill the same condensed positions.
+fn pair_values(
+    hierarchy: &ClusterHierarchy,
+    ids: Option<&[usize]>,
+    value: impl Fn(usize, f64) -> f64,
+) -> Vec<f64> {
+    let n = hierarchy.original_n;
+    let mut common: Vec<usize> = (0..n).collect();
+    if let Some(ids) = ids {
+        for (leaf, &id) in ids.iter().enumerate() {
+            common[id] = leaf;
+        }
+    }
+
+    let view = hierarchy.tree_view();
+    let mut values = vec![0.0; n * n.saturating_sub(1) / 2];
+    for (t, merge) in hierarchy.merges.iter().enumerate() {
+        let pair_value = value(t, merge.dist);
+        for &left in view.leaves(merge.cid1) {
+            for &right in view.leaves(merge.cid2) {
+                let (i, j) = match common[left] < common[right] {
+                    true => (common[left], common[right]),
+                    false => (common[right], common[left]),
+                };
+                values[n * i - i * (i + 1) / 2 + (j - i - 1)] = pair_value;
+            }
+        }
+    }
+
+    return values;
+}
+
+/// Pearson correlation of the pair values, an error when either side is constant
+fn correlation(first: &[f64], second: &[f64]) -> Result<f64, Box<dyn Error>> {
+    if first.len() < 2 {
+        return Err("The correlation needs at least 3 leaves".into());
+    }
+    let constant = |values: &[f64]| values.iter().all(|&v| v == values[0]);
+    if constant(first) || constant(second) {
+        return Err(
+            "The correlation is undefined when all merges of a tree are at the same height".into(),
+        );
+    }
+
+    return Ok(pearson_r(first, second, false));
+}
+
+/// Ranks starting at 1, tied values get their average rank
+fn ranks(values: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&a, &b| values[a].total_cmp(&values[b]));
+
+    let mut ranks = vec![0.0; values.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start + 1;
+        while end < order.len() && values[order[end]] == values[order[start]] {
+            end += 1;
+        }
+        let rank = (start + end + 1) as f64 / 2.0;
+        for &idx in &order[start..end] {
+            ranks[idx] = rank;
+        }
+        start = end;
+    }
+
+    return ranks;
+}
+
+/// Fowlkes–Mallows index of two flat clusterings, `ids` maps the first leaves to the second
+fn fowlkes_mallows_index(first: &[usize], second: &[usize], ids: &[usize]) -> f64 {
+    let n = first.len() as f64;
+
+    let mut table: HashMap<(usize, usize), f64> = HashMap::new();
+    let mut first_sizes: HashMap<usize, f64> = HashMap::new();
+    let mut second_sizes: HashMap<usize, f64> = HashMap::new();
+    for (leaf, &id) in ids.iter().enumerate() {
+        *table.entry((first[leaf], second[id])).or_default() += 1.0;
+        *first_sizes.entry(first[leaf]).or_default() += 1.0;
+        *second_sizes.entry(second[id]).or_default() += 1.0;
+    }
+
+    let squares = |counts: &mut dyn Iterator<Item = &f64>| counts.map(|c| c * c).sum::<f64>();
+    let t = squares(&mut table.values()) - n;
+    let p = squares(&mut first_sizes.values()) - n;
+    let q = squares(&mut second_sizes.values()) - n;
+
+    return t / (p * q).sqrt();
+}
@@ -0,0 +1,108 @@
+use std::{error::Error, fs::File, io::Write};
+
+use super::{utils::csv_field, ClusterHierarchy};
+
+impl ClusterHierarchy {
+    /// Cuts the tree into `k` flat clusters
+    ///
+    /// Returns one cluster number per leaf (indexed by leaf id). Clusters are numbered from 1
+    /// in the order they first appear in the leaf ordering.
+    pub fn flat_clusters(&self, k: usize) -> Result<Vec<usize>, Box<dyn Error>> {
+        let n = self.original_n;
+        if k == 0 || k > n {
+            return Err(format!(
+                "Number of clusters must be between 1 and {} but got {}",
+                n, k
+            )
+            .into());
+        }
+
+        return Ok(self.apply_merges(n - k));
+    }
+
+    /// Cuts the tree at `height`, every merge at or below that distance is kept
+    ///
+    /// Returns one cluster number per leaf (indexed by leaf id), numbered like `flat_clusters`.
+    pub fn flat_clusters_at_height(&self, height: f64) -> Vec<usize> {
+        let kept = self.merges.iter().take_while(|m| m.dist <= height).count();
+        return self.apply_merges(kept);
+    }
+
+    /// Writes flat cluster assignments as csv with a `leaf,label,cluster` header
+    pub fn write_flat_clusters(
+        &self,
+        fname: &str,
+        clusters: &[usize],
+    ) -> Result<(), Box<dyn Error>> {
+        if clusters.len() != self.original_n {
+            return Err(format!(
+                "Expected {} cluster assignments but got {}",
+                self.original_n,
+                clusters.len()
+            )
+            .into());
+        }
+
+        let mut csv = String::from("leaf,label,cluster\n");
+        for (leaf, cluster) in clusters.iter().enumerate() {
+            csv.push_str(&format!(
+                "{},{},{}\n",
+                leaf,
+                csv_field(&self.leaf_label(leaf)),
+                cluster
+            ));
+        }
+
+        let mut file = File::create(fname)?;
+        file.write_all(csv.as_bytes())?;
+
+        return Ok(());
+    }
+
+    /// Applies the first `num_merges` merges and numbers the resulting clusters
+    fn apply_merges(&self, num_merges: usize) -> Vec<usize> {
+        let n = self.original_n;
+
+        // union-find over the leaves, `rep` maps a cluster id to one of its leaves
+        let mut parent: Vec<usize> = (0..n).collect();
+        let mut rep: Vec<usize> = (0..n).collect();
+        rep.resize(n + self.merges.len(), 0);
+
+        fn find(parent: &mut [usize], mut leaf: usize) -> usize {
+            while parent[leaf] != leaf {
+                parent[leaf] = parent[parent[leaf]];
+                leaf = parent[leaf];
+            }
+            return leaf;
+        }
+
+        for merge in self.merges.iter().take(num_merges) {
+            let root1 = find(&mut parent, rep[merge.cid1]);
+            let root2 = find(&mut parent, rep[merge.cid2]);
+            parent[root2] = root1;
+
+            if merge.new_cid >= rep.len() {
+                rep.resize(merge.new_cid + 1, 0);
+            }
+            rep[merge.new_cid] = root1;
+        }
+
+        let mut number = vec![0usize; n];
+        let mut clusters = vec![0usize; n];
+        let mut next = 1;
+        let mut order = self.leaf_ordering();
+        if order.len() != n {
+            order = (0..n).collect();
+        }
+        for leaf in order {
+            let root = find(&mut parent, leaf);
+            if number[root] == 0 {
+                number[root] = next;
+                next += 1;
+            }
+            clusters[leaf] = number[root];
+        }
+
+        return clusters;
+    }
+}
@@ -0,0 +1,147 @@
+use std::{collections::BTreeMap, error::Error, fs::File, io::Write};
+
+use serde_json::Value;
+
+use super::{
+    tree_json::{json_number, json_string},
+    ClusterHierarchy,
+};
+
+/// Keys written by `to_d3_json` that metadata fields may not use
+const RESERVED_KEYS: [&str; 6] = ["name", "height", "size", "leaf", "cluster", "children"];
+
+/// A piece of output still to be written by `to_d3_json`
+enum D3Step {
+    Node(usize),
+    Text(&'static str),
+}
+
+/// Extra leaf information for `ClusterHierarchy::to_d3_json`
+#[derive(Debug, Clone, Default)]
+pub struct D3Options {
+    /// flat cluster id per leaf (e.g. from `flat_clusters`), internal nodes get the id when
+    /// all of their leaves share it
+    pub clusters: Option<Vec<usize>>,
+    /// metadata fields copied onto the leaves, every field holds one value per leaf
+    pub metadata: BTreeMap<String, Vec<Value>>,
+}
+
+impl ClusterHierarchy {
+    /// # Returns the tree as d3-hierarchy compatible json
+    ///
+    /// Every node is written as `{name, height, size, children}` so the output can be passed
+    /// straight to `d3.hierarchy`. Leaves are named after their label (the leaf index when
+    /// unlabelled), have no `children` and carry their `leaf` index, the `cluster` id and the
+    /// metadata fields. Internal nodes are named `N<cid>` like the Newick export. The json is
+    /// compact and written without recursion, so deep single linkage chains are safe.
+    ///
+    /// ### params:
+    /// - options: `D3Options` with the flat cluster assignments and the leaf metadata
+    pub fn to_d3_json(&self, options: &D3Options) -> Result<String, Box<dyn Error>> {
+        let n = self.original_n;
+        if let Some(clusters) = &options.clusters {
+            if clusters.len() != n {
+                return Err(format!(
+                    "Expected {} cluster assignments but got {}",
+                    n,
+                    clusters.len()
+                )
+                .into());
+            }
+        }
+        for (field, values) in &options.metadata {
+            if RESERVED_KEYS.contains(&field.as_str()) {
+                return Err(format!("Metadata field '{}' is a reserved key", field).into());
+            }
+            if values.len() != n {
+                return Err(format!(
+                    "Metadata field '{}' has {} values but expected {}",
+                    field,
+                    values.len(),
+                    n
+                )
+                .into());
+            }
+        }
+
+        if n == 0 {
+            return Err("Hierarchy has no root".into());
+        }
+
+        // cluster id of every node whose leaves all share one
+        let view = self.tree_view();
+        let max_cid = self.merges.iter().map(|m| m.new_cid).max().unwrap_or(0);
+        let mut clusters: Vec<Option<usize>> = vec![None; (max_cid + 1).max(n)];
+        if let Some(ids) = &options.clusters {
+            for node in view.postorder() {
+                clusters[node] = match view.children(node) {
+                    Some((left, right)) if clusters[left] == clusters[right] => clusters[left],
+                    Some(_) => None,
+                    None => Some(ids[node]),
+                };
+            }
+        }
+
+        // depth first with an explicit stack, so deep single linkage chains don't overflow
+        let mut json = String::new();
+        let mut stack = vec![D3Step::Node(view.root())];
+        while let Some(step) = stack.pop() {
+            let node = match step {
+                D3Step::Text(text) => {
+                    json.push_str(text);
+                    continue;
+                }
+                D3Step::Node(node) => node,
+            };
+
+            let name = match view.is_leaf(node) {
+                true => self.leaf_label(node),
+                false => format!("N{}", node),
+            };
+            json.push_str(&format!(
+                "{{\"name\":{},\"height\":{},\"size\":{}",
+                json_string(&name),
+                json_number(view.height(node)),
+                view.size(node)
+            ));
+            if view.is_leaf(node) {
+                json.push_str(&format!(",\"leaf\":{}", node));
+            }
+            if let Some(cluster) = clusters[node] {
+                json.push_str(&format!(",\"cluster\":{}", cluster));
+            }
+
+            match view.children(node) {
+                Some((left, right)) => {
+                    json.push_str(",\"children\":[");
+                    stack.push(D3Step::Text("]}"));
+                    stack.push(D3Step::Node(right));
+                    stack.push(D3Step::Text(","));
+                    stack.push(D3Step::Node(left));
+                }
+                None => {
+                    for (field, values) in &options.metadata {
+                        json.push_str(&format!(
+                            ",{}:{}",
+                            json_string(field),
+                            serde_json::to_string(&values[node])?
+                        ));
+                    }
+                    json.push('}');
+                }
+            }
+        }
+
+        return Ok(json);
+    }
+
+    /// Writes the d3-hierarchy json to `fname`, see `to_d3_json`
+    pub fn write_d3_json(&self, fname: &str, options: &D3Options) -> Result<(), Box<dyn Error>> {
+        let json_str = self.to_d3_json(options)?;
+
+        let mut file = File::create(fname)?;
+        file.write_all(json_str.as_bytes())?;
+
+        return Ok(());
+    }
+}
@@ -0,0 +1,171 @@
+use std::{error::Error, fs::File, io::Write};
+
+use super::ClusterHierarchy;
+
+/// Smallest edge `len` written by `to_dot`, neato/fdp need positive lengths
+const MIN_EDGE_LEN: f64 = 1e-3;
+
+/// Options for `ClusterHierarchy::to_dot`
+#[derive(Debug, Clone)]
+pub struct DotOptions {
+    /// internal nodes merged below this height are drawn as a single collapsed node
+    pub collapse_below: Option<f64>,
+    /// flat cluster id per leaf (e.g. from `flat_clusters`) used to fill the nodes, internal
+    /// nodes are filled when all of their leaves share a cluster
+    pub clusters: Option<Vec<usize>>,
+    /// fill colors, cluster `k` gets `palette[(k - 1) % palette.len()]`
+    pub palette: Vec<String>,
+    /// write the branch lengths as edge labels
+    pub edge_labels: bool,
+}
+
+impl Default for DotOptions {
+    fn default() -> Self {
+        DotOptions {
+            collapse_below: None,
+            clusters: None,
+            palette: [
+                "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2",
+                "#7f7f7f", "#bcbd22", "#17becf",
+            ]
+            .iter()
+            .map(|c| c.to_string())
+            .collect(),
+            edge_labels: false,
+        }
+    }
+}
+
+impl ClusterHierarchy {
+    /// # Returns the dendrogram as a Graphviz DOT digraph
+    ///
+    /// Nodes are named `n<cid>`. Leaves are boxes labelled with their leaf label, internal nodes
+    /// show `N<cid>` and their merge height, and every edge carries the branch length (the
+    /// height difference between parent and child) as its `len` so neato/fdp layouts follow
+    /// the tree distances (zero lengths are raised to `MIN_EDGE_LEN`, dot ignores `len`).
+    /// Collapsed subtrees are drawn as triangles with their leaf count. Nodes of a flat cluster
+    /// `k` get `class="cluster_k"`, which ends up on their group in svg output.
+    ///
+    /// ### params:
+    /// - options: `DotOptions` with the collapse height, cluster coloring and edge labels
+    pub fn to_dot(&self, options: &DotOptions) -> Result<String, Box<dyn Error>> {
+        let n = self.original_n;
+        if n == 0 {
+            return Err("Hierarchy has no leaves".into());
+        }
+        if let Some(clusters) = &options.clusters {
+            if clusters.len() != n {
+                return Err(format!(
+                    "Expected {} cluster assignments but got {}",
+                    n,
+                    clusters.len()
+                )
+                .into());
+            }
+            if options.palette.is_empty() {
+                return Err("Cluster coloring needs a non-empty palette".into());
+            }
+        }
+
+        // children, height, size and shared cluster id of every cluster id
+        let max_cid = self.merges.iter().map(|m| m.new_cid).max().unwrap_or(0);
+        let len = (max_cid + 1).max(n);
+        let mut children: Vec<Option<(usize, usize)>> = vec![None; len];
+        let mut heights = vec![0.0; len];
+        let mut sizes = vec![1; len];
+        let mut clusters: Vec<Option<usize>> = vec![None; len];
+        if let Some(ids) = &options.clusters {
+            for leaf in 0..n {
+                clusters[leaf] = Some(ids[leaf]);
+            }
+        }
+        for merge in &self.merges {
+            children[merge.new_cid] = Some((merge.cid1, merge.cid2));
+            heights[merge.new_cid] = merge.dist;
+            sizes[merge.new_cid] = merge.size;
+            if clusters[merge.cid1] == clusters[merge.cid2] {
+                clusters[merge.new_cid] = clusters[merge.cid1];
+            }
+        }
+
+        let mut dot = String::from("digraph dendrogram {\n");
+        dot.push_str("    node [fontname=\"Helvetica\"];\n");
+        dot.push_str("    edge [arrowhead=none];\n");
+
+        let root = self.merges.last().map(|m| m.new_cid).unwrap_or(0);
+        let mut stack = vec![root];
+        while let Some(cid) = stack.pop() {
+            let height = heights[cid];
+            let collapsed = children[cid].is_some()
+                && options.collapse_below.is_some_and(|below| height < below);
+
+            let mut attrs = match children[cid] {
+                None => format!("shape=box, label=\"{}\"", escape(&self.leaf_label(cid))),
+                Some(_) if collapsed => format!(
+                    "shape=triangle, label=\"N{}\\n{} leaves\\nh={}\"",
+                    cid,
+                    sizes[cid],
+                    format_height(height)
+                ),
+                Some(_) => format!(
+                    "shape=ellipse, label=\"N{}\\nh={}\"",
+                    cid,
+                    format_height(height)
+                ),
+            };
+            if let Some(cluster) = clusters[cid] {
+                let color = &options.palette[cluster.saturating_sub(1) % options.palette.len()];
+                attrs.push_str(&format!(
+                    ", style=filled, fillcolor=\"{}\", class=\"cluster_{}\"",
+                    escape(color),
+                    cluster
+                ));
+            }
+            dot.push_str(&format!("    n{} [{}];\n", cid, attrs));
+
+            if collapsed {
+                continue;
+            }
+            if let Some((left, right)) = children[cid] {
+                for child in [left, right] {
+                    let branch = height - heights[child];
+                    dot.push_str(&format!(
+                        "    n{} -> n{} [len={}",
+                        cid,
+                        child,
+                        branch.max(MIN_EDGE_LEN)
+                    ));
+                    if options.edge_labels {
+                        dot.push_str(&format!(", label=\"{}\"", format_height(branch)));
+                    }
+                    dot.push_str("];\n");
+                }
+                stack.push(right);
+                stack.push(left);
+            }
+        }
+        dot.push_str("}\n");
+
+        return Ok(dot);
+    }
+
+    /// Writes the DOT graph to `fname`, see `to_dot`
+    pub fn write_dot(&self, fname: &str, options: &DotOptions) -> Result<(), Box<dyn Error>> {
+        let dot = self.to_dot(options)?;
+
+        let mut file = File::create(fname)?;
+        file.write_all(dot.as_bytes())?;
+
+        return Ok(());
+    }
+}
+
+/// Escapes a string for a double quoted DOT id
+fn escape(text: &str) -> String {
+    return text.replace('\\', "\\\\").replace('"', "\\\"");
+}
+
+/// Short height for node and edge labels
+fn format_height(height: f64) -> String {
+    return format!("{:.3}", height);
+}
@@ -0,0 +1,268 @@
+use std::{error::Error, fs::File, io::Write};
+
+use super::{ordering::merge_lookup, ClusterHierarchy};
+
+impl ClusterHierarchy {
+    /// Builds a hierarchy from a SciPy style `(n - 1) x 4` linkage matrix
+    ///
+    /// The matrix is validated before use: ids must be integers referring to a leaf or an
+    /// earlier row, every cluster may only be merged once, distances must be finite and
+    /// non-decreasing and the counts (when non-zero) must match the merged cluster sizes.
+    pub fn from_linkage_matrix(linkage: &[[f64; 4]]) -> Result<ClusterHierarchy, Box<dyn Error>> {
+        if linkage.is_empty() {
+            return Err("Linkage matrix needs at least one row".into());
+        }
+
+        let n = linkage.len() + 1;
+        let mut merged = vec![false; 2 * n - 1];
+        let mut sizes = vec![1usize; 2 * n - 1];
+        let mut merges: Vec<(usize, usize, f64, usize)> = Vec::with_capacity(n - 1);
+        let mut prev_dist = f64::MIN;
+
+        for (i, row) in linkage.iter().enumerate() {
+            let new_cid = n + i;
+            let mut ids = [0usize; 2];
+            for (k, &id) in row[..2].iter().enumerate() {
+                if id.fract() != 0.0 || id < 0.0 || id >= new_cid as f64 {
+                    return Err(format!("Row {}: invalid cluster id {}", i, id).into());
+                }
+                ids[k] = id as usize;
+            }
+            let [cid1, cid2] = ids;
+
+            if cid1 == cid2 {
+                return Err(format!("Row {}: cluster {} merged with itself", i, cid1).into());
+            }
+            for cid in ids {
+                if merged[cid] {
+                    return Err(format!("Row {}: cluster {} merged more than once", i, cid).into());
+                }
+                merged[cid] = true;
+            }
+
+            let dist = row[2];
+            if !dist.is_finite() {
+                return Err(format!("Row {}: distance {} is not finite", i, dist).into());
+            }
+            if dist < prev_dist {
+                return Err(format!("Row {}: distance {} is not monotonic", i, dist).into());
+            }
+            prev_dist = dist;
+
+            sizes[new_cid] = sizes[cid1] + sizes[cid2];
+            if row[3] != 0.0 && row[3] != sizes[new_cid] as f64 {
+                return Err(format!(
+                    "Row {}: count {} does not match cluster size {}",
+                    i, row[3], sizes[new_cid]
+                )
+                .into());
+            }
+
+            merges.push((cid1, cid2, dist, new_cid));
+        }
+
+        return Ok(ClusterHierarchy::new(&merges, n));
+    }
+
+    /// Loads a linkage matrix from a numpy `.npy` file (`<f8` or `<f4`)
+    pub fn load_linkage_npy(fname: &str) -> Result<ClusterHierarchy, Box<dyn Error>> {
+        let bytes = std::fs::read(fname)?;
+        let linkage = parse_npy(&bytes)?;
+        return ClusterHierarchy::from_linkage_matrix(&linkage);
+    }
+
+    /// Loads a linkage matrix from a comma or whitespace delimited text file
+    ///
+    /// A non-numeric first line is treated as a header and skipped.
+    pub fn load_linkage_csv(fname: &str) -> Result<ClusterHierarchy, Box<dyn Error>> {
+        let text = std::fs::read_to_string(fname)?;
+
+        let mut linkage: Vec<[f64; 4]> = Vec::new();
+        for (line_no, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let vals: Result<Vec<f64>, _> = line
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|v| !v.is_empty())
+                .map(|v| v.parse::<f64>())
+                .collect();
+
+            match vals {
+                Ok(vals) if vals.len() == 4 => linkage.push([vals[0], vals[1], vals[2], vals[3]]),
+                Ok(vals) => {
+                    return Err(format!(
+                        "Line {}: expected 4 columns but got {}",
+                        line_no + 1,
+                        vals.len()
+                    )
+                    .into())
+                }
+                Err(_) if line_no == 0 => continue,
+                Err(e) => return Err(format!("Line {}: {}", line_no + 1, e).into()),
+            }
+        }
+
+        return ClusterHierarchy::from_linkage_matrix(&linkage);
+    }
+
+    /// Returns the SciPy style `(n - 1) x 4` linkage matrix
+    ///
+    /// Every row is `[id1, id2, dist, count]` where leaves are `0..n` and the cluster formed
+    /// by row `i` has the id `n + i`, so the output can be passed directly to
+    /// `scipy.cluster.hierarchy.dendrogram`.
+    pub fn linkage_matrix(&self) -> Vec<[f64; 4]> {
+        let n = self.original_n;
+        let merge_idx = merge_lookup(&self.merges, n);
+        let scipy_id = |cid: usize| -> f64 {
+            if cid < n {
+                return cid as f64;
+            }
+            return (n + merge_idx[cid]) as f64;
+        };
+
+        return self
+            .merges
+            .iter()
+            .map(|m| [scipy_id(m.cid1), scipy_id(m.cid2), m.dist, m.size as f64])
+            .collect();
+    }
+
+    /// Returns the linkage matrix as a json formatted list of rows
+    pub fn linkage_to_json(&self) -> Result<String, Box<dyn Error>> {
+        return Ok(serde_json::to_string_pretty(&self.linkage_matrix())?);
+    }
+
+    /// Writes the linkage matrix as json
+    pub fn write_linkage_json(&self, fname: &str) -> Result<(), Box<dyn Error>> {
+        let json_str = self.linkage_to_json()?;
+
+        let mut file = File::create(fname)?;
+        file.write_all(json_str.as_bytes())?;
+
+        return Ok(());
+    }
+
+    /// Writes the linkage matrix as csv with an `id1,id2,dist,count` header
+    pub fn write_linkage_csv(&self, fname: &str) -> Result<(), Box<dyn Error>> {
+        let mut csv = String::from("id1,id2,dist,count\n");
+        for row in self.linkage_matrix() {
+            csv.push_str(&format!("{},{},{},{}\n", row[0], row[1], row[2], row[3]));
+        }
+
+        let mut file = File::create(fname)?;
+        file.write_all(csv.as_bytes())?;
+
+        return Ok(());
+    }
+
+    /// Writes the linkage matrix as a numpy `.npy` file (`<f8`, C order)
+    pub fn write_linkage_npy(&self, fname: &str) -> Result<(), Box<dyn Error>> {
+        let rows = self.linkage_matrix();
+
+        let mut file = File::create(fname)?;
+        file.write_all(&npy_header(rows.len(), 4))?;
+        for row in rows {
+            for val in row {
+                file.write_all(&val.to_le_bytes())?;
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+/// Builds a version 1.0 `.npy` header for a 2D little endian f64 array
+fn npy_header(nrows: usize, ncols: usize) -> Vec<u8> {
+    let mut dict = format!(
+        "{{'descr': '<f8', 'fortran_order': False, 'shape': ({}, {}), }}",
+        nrows, ncols
+    );
+
+    // magic (6) + version (2) + header length (2) + dict + newline must be a multiple of 64
+    let unpadded = 10 + dict.len() + 1;
+    let padding = (64 - unpadded % 64) % 64;
+    dict.push_str(&" ".repeat(padding));
+    dict.push('\n');
+
+    let mut header = b"\x93NUMPY\x01\x00".to_vec();
+    header.extend_from_slice(&(dict.len() as u16).to_le_bytes());
+    header.extend_from_slice(dict.as_bytes());
+
+    return header;
+}
+
+/// Parses a 2D `(rows, 4)` float array out of `.npy` bytes
+fn parse_npy(bytes: &[u8]) -> Result<Vec<[f64; 4]>, Box<dyn Error>> {
+    if bytes.len() < 10 || &bytes[..6] != b"\x93NUMPY" {
+        return Err("Not a .npy file".into());
+    }
+
+    let (header_len, offset) = match bytes[6] {
+        1 => (u16::from_le_bytes([bytes[8], bytes[9]]) as usize, 10),
+        2 | 3 if bytes.len() >= 12 => (
+            u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]) as usize,
+            12,
+        ),
+        v => return Err(format!("Unsupported .npy version {}", v).into()),
+    };
+    if bytes.len() < offset + header_len {
+        return Err("Truncated .npy header".into());
+    }
+    let header = std::str::from_utf8(&bytes[offset..offset + header_len])?;
+    let data = &bytes[offset + header_len..];
+
+    let item_size = if header.contains("'<f8'") {
+        8
+    } else if header.contains("'<f4'") {
+        4
+    } else {
+        return Err(format!("Unsupported .npy dtype in header {}", header.trim()).into());
+    };
+    let fortran_order = header.contains("'fortran_order': True");
+
+    let shape_start = header
+        .find("'shape': (")
+        .ok_or("Missing shape in .npy header")?
+        + 10;
+    let shape_end = shape_start + header[shape_start..].find(')').ok_or("Bad .npy shape")?;
+    let shape: Vec<usize> = header[shape_start..shape_end]
+        .split(',')
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .map(|v| v.parse::<usize>())
+        .collect::<Result<_, _>>()?;
+    if shape.len() != 2 || shape[1] != 4 {
+        return Err(format!("Expected a (n, 4) array but got shape {:?}", shape).into());
+    }
+
+    let nrows = shape[0];
+    if data.len() < nrows * 4 * item_size {
+        return Err("Truncated .npy data".into());
+    }
+
+    let value = |idx: usize| -> f64 {
+        let start = idx * item_size;
+        if item_size == 8 {
+            return f64::from_le_bytes(data[start..start + 8].try_into().unwrap());
+        }
+        return f32::from_le_bytes(data[start..start + 4].try_into().unwrap()) as f64;
+    };
+
+    let linkage = (0..nrows)
+        .map(|r| {
+            let mut row = [0.0; 4];
+            for (c, val) in row.iter_mut().enumerate() {
+                *val = match fortran_order {
+                    true => value(c * nrows + r),
+                    false => value(r * 4 + c),
+                };
+            }
+            row
+        })
+        .collect();
+
+    return Ok(linkage);
+}
@@ -1,4 +1,4 @@
-use core::f64;
+use std::str::FromStr;
 
 use serde::{Deserialize, Serialize};
 
@@ -11,6 +11,25 @@ pub enum LinkageMethod {
     Average,
 }
 
+impl FromStr for LinkageMethod {
+    type Err = String;
+
+    /// Parses `single`, `complete` or `average` (case insensitive)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "single" => return Ok(LinkageMethod::Single),
+            "complete" => return Ok(LinkageMethod::Complete),
+            "average" => return Ok(LinkageMethod::Average),
+            _ => {
+                return Err(format!(
+                    "Unknown linkage method '{}', expected single, complete or average",
+                    s
+                ))
+            }
+        }
+    }
+}
+
 pub(in crate::clustering) fn calc_dist(
     cid1: usize,
     cid2: usize,
@@ -26,7 +45,7 @@ pub(in crate::clustering) fn calc_dist(
             let mut dist = f64::MAX;
             for &i in item1 {
                 for &j in item2 {
-                    let d = distances.get_distances(i, j).clone();
+                    let d = *distances.get_distances(i, j);
                     if d < dist {
                         dist = d;
                     }
@@ -39,7 +58,7 @@ pub(in crate::clustering) fn calc_dist(
             let mut dist = f64::MIN;
             for &i in item1 {
                 for &j in item2 {
-                    let d = distances.get_distances(i, j).clone();
+                    let d = *distances.get_distances(i, j);
                     if d > dist {
                         dist = d;
                     }
@@ -54,7 +73,7 @@ pub(in crate::clustering) fn calc_dist(
             let mut count = 0.0;
             for &i in item1 {
                 for &j in item2 {
-                    total += distances.get_distances(i, j).clone();
+                    total += *distances.get_distances(i, j);
                     count += 1.0;
                 }
             }
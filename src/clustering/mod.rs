@@ -1,10 +1,30 @@
 mod agg_clustering;
+mod compare;
+mod cut;
+mod d3;
+mod dot;
+mod linkage_matrix;
 mod linkages;
+mod newick;
+mod ordering;
+mod subtree;
+mod tanglegram;
+mod tree_json;
+mod tree_view;
 mod utils;
 
-pub use agg_clustering::hierarchical_clustering;
+pub use agg_clustering::{hierarchical_clustering, hierarchical_clustering_from_array};
+pub use compare::{
+    bakers_gamma, cophenetic_correlation, fowlkes_mallows, fowlkes_mallows_curve, robinson_foulds,
+};
+pub use d3::D3Options;
+pub use dot::DotOptions;
 pub use linkages::LinkageMethod;
+use ndarray::ArrayView2;
+pub use ordering::WeightAggregation;
 use serde::{Deserialize, Serialize};
+pub use tanglegram::{untangle, Tanglegram, UntangleMethod};
+pub use tree_view::TreeView;
 pub use utils::DendrogramNode;
 
 #[derive(Debug, Clone)]
@@ -36,22 +56,41 @@ impl ClusterMap {
     }
 }
 
+/// Condensed upper triangle of a square distance matrix, row by row
 #[derive(Debug, Clone)]
 pub(in crate::clustering) struct Distances {
-    distances: Vec<Vec<f64>>,
+    distances: Vec<f64>,
     len: usize,
 }
 
 impl Distances {
     pub fn new(dists: &[Vec<f64>]) -> Self {
-        Distances {
-            distances: dists.to_vec(),
-            len: dists.len(),
+        let len = dists.len();
+        let mut distances = Vec::with_capacity(len * len.saturating_sub(1) / 2);
+        for (i, row) in dists.iter().enumerate() {
+            distances.extend_from_slice(&row[i + 1..]);
         }
+
+        Distances { distances, len }
+    }
+
+    /// Copies the upper triangle straight out of the view
+    pub fn from_array(dists: ArrayView2<f64>) -> Self {
+        let len = dists.nrows();
+        let mut distances = Vec::with_capacity(len * len.saturating_sub(1) / 2);
+        for (i, row) in dists.rows().into_iter().enumerate() {
+            distances.extend(row.iter().skip(i + 1));
+        }
+
+        Distances { distances, len }
     }
 
     pub fn get_distances(&self, i: usize, j: usize) -> &f64 {
-        &self.distances[i][j]
+        if i == j {
+            return &0.0;
+        }
+        let (i, j) = if i < j { (i, j) } else { (j, i) };
+        &self.distances[self.len * i - i * (i + 1) / 2 + (j - i - 1)]
     }
 
     pub fn len(&self) -> usize {
@@ -60,11 +99,52 @@ impl Distances {
 }
 
 /// Output for Agglomerative clustering
+///
+/// Serialized as the merge list, leaf count and labels. The dendrogram tree is rebuilt (and
+/// the merges validated) on deserialization so arbitrarily deep trees round trip safely.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "SavedHierarchy")]
 pub struct ClusterHierarchy {
     merges: Vec<Merge>,
     original_n: usize,
+    #[serde(skip)]
     tree: Option<DendrogramNode>,
+    labels: Option<Vec<String>>,
+}
+
+/// Serialized fields of `ClusterHierarchy`
+#[derive(Deserialize)]
+struct SavedHierarchy {
+    merges: Vec<Merge>,
+    original_n: usize,
+    #[serde(default)]
+    labels: Option<Vec<String>>,
+}
+
+impl TryFrom<SavedHierarchy> for ClusterHierarchy {
+    type Error = String;
+
+    fn try_from(saved: SavedHierarchy) -> Result<Self, Self::Error> {
+        // without merges only an empty or single leaf hierarchy is consistent
+        if saved.merges.is_empty() && saved.original_n <= 1 {
+            return Ok(ClusterHierarchy {
+                merges: vec![],
+                original_n: saved.original_n,
+                tree: None,
+                labels: saved.labels,
+            });
+        }
+        if saved.original_n != saved.merges.len() + 1 {
+            return Err(format!(
+                "{} merges can't join {} leaves",
+                saved.merges.len(),
+                saved.original_n
+            ));
+        }
+
+        return ClusterHierarchy::from_merges(&saved.merges, saved.labels)
+            .map_err(|e| e.to_string());
+    }
 }
 
 /// Struct to represent merges
@@ -74,4 +154,6 @@ pub struct Merge {
     cid2: usize,
     dist: f64,
     new_cid: usize,
+    #[serde(default)]
+    size: usize,
 }
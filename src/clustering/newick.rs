@@ -0,0 +1,429 @@
+use std::{collections::HashMap, error::Error, fs::File, io::Write};
+
+use super::{ClusterHierarchy, DendrogramNode};
+
+/// Characters that force a Newick label to be quoted
+const NEWICK_META: &[char] = &['(', ')', '[', ']', '\'', ':', ';', ',', '_'];
+
+/// Relative tolerance on root-to-leaf path lengths when checking that a tree is ultrametric
+const ULTRAMETRIC_TOL: f64 = 1e-4;
+
+/// A node while parsing Newick, stored in pre-order so parents come before children
+struct NewickNode {
+    label: String,
+    length: Option<f64>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+}
+
+impl ClusterHierarchy {
+    /// Returns the hierarchy as a Newick string
+    ///
+    /// Leaves are written with their label (or leaf index when the hierarchy is unlabelled) and
+    /// every node gets a branch length equal to the height difference with its parent.
+    ///
+    /// ### params:
+    /// - labels: optional leaf labels indexed by leaf id, overriding the hierarchy's labels
+    /// - internal_names: name internal nodes `N<cid>`
+    pub fn to_newick(
+        &self,
+        labels: Option<&[String]>,
+        internal_names: bool,
+    ) -> Result<String, Box<dyn Error>> {
+        let root = self.tree.as_ref().ok_or("No Tree found!")?;
+        let labels = labels.or(self.labels.as_deref());
+        if let Some(labels) = labels {
+            if labels.len() != self.original_n {
+                return Err(format!(
+                    "Expected {} labels but got {}",
+                    self.original_n,
+                    labels.len()
+                )
+                .into());
+            }
+        }
+
+        let mut newick = String::new();
+
+        // (node, parent height, whether the node's children have been written)
+        let mut stack: Vec<(&DendrogramNode, f64, bool)> = vec![(root, root.distance, false)];
+        while let Some((node, parent_dist, visited)) = stack.pop() {
+            match (&node.left, &node.right) {
+                (Some(left), Some(right)) if !visited => {
+                    newick.push('(');
+                    stack.push((node, parent_dist, true));
+                    stack.push((right, node.distance, false));
+                    stack.push((left, node.distance, false));
+                    continue;
+                }
+                (Some(_), Some(_)) => {
+                    newick.push(')');
+                    if internal_names {
+                        newick.push_str(&format!("N{}", node.cid));
+                    }
+                }
+                _ => {
+                    let label = match labels {
+                        Some(labels) => quote_label(&labels[node.cid]),
+                        None => node.cid.to_string(),
+                    };
+                    newick.push_str(&label);
+                }
+            }
+
+            if !std::ptr::eq(node, root) {
+                newick.push_str(&format!(":{}", parent_dist - node.distance));
+            }
+
+            // separate from the next sibling when this node was a left child
+            if let Some((_, _, false)) = stack.last() {
+                newick.push(',');
+            }
+        }
+        newick.push(';');
+
+        return Ok(newick);
+    }
+
+    /// Writes the Newick tree to a file
+    pub fn write_newick(
+        &self,
+        fname: &str,
+        labels: Option<&[String]>,
+        internal_names: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        let newick = self.to_newick(labels, internal_names)?;
+
+        let mut file = File::create(fname)?;
+        file.write_all(newick.as_bytes())?;
+        file.write_all(b"\n")?;
+
+        return Ok(());
+    }
+
+    /// Builds a hierarchy from a Newick string
+    ///
+    /// The tree must be binary, have branch lengths on every non-root node and be ultrametric
+    /// (all leaves at the same distance from the root). Merge heights are the distances from
+    /// the leaves and the Newick leaf names become the hierarchy's labels.
+    ///
+    /// ### params:
+    /// - newick: the Newick string
+    /// - leaf_order: optional labels giving the leaf id of every Newick leaf (label `i` gets
+    ///   id `i`); leaves are numbered in order of appearance otherwise
+    pub fn from_newick(
+        newick: &str,
+        leaf_order: Option<&[String]>,
+    ) -> Result<ClusterHierarchy, Box<dyn Error>> {
+        let nodes = parse_newick(newick)?;
+
+        // distance of every node from the root
+        let mut depth = vec![0.0; nodes.len()];
+        for (idx, node) in nodes.iter().enumerate() {
+            if let Some(parent) = node.parent {
+                let length = node
+                    .length
+                    .ok_or_else(|| format!("Node '{}' has no branch length", node.label))?;
+                if !length.is_finite() || length < 0.0 {
+                    return Err(format!(
+                        "Node '{}' has invalid branch length {}",
+                        node.label, length
+                    )
+                    .into());
+                }
+                depth[idx] = depth[parent] + length;
+            }
+            match node.children.len() {
+                0 | 2 => {}
+                k => {
+                    return Err(format!(
+                        "Node '{}' has {} children, tree must be binary",
+                        node.label, k
+                    )
+                    .into())
+                }
+            }
+        }
+
+        let leaves: Vec<usize> = (0..nodes.len())
+            .filter(|&i| nodes[i].children.is_empty())
+            .collect();
+        if leaves.len() < 2 {
+            return Err("Newick tree needs at least two leaves".into());
+        }
+
+        let tree_height = leaves.iter().map(|&i| depth[i]).fold(0.0, f64::max);
+        for &leaf in &leaves {
+            if (tree_height - depth[leaf]).abs() > ULTRAMETRIC_TOL * tree_height.max(1.0) {
+                return Err(format!(
+                    "Tree is not ultrametric: leaf '{}' is at depth {} but the tree height is {}",
+                    nodes[leaf].label, depth[leaf], tree_height
+                )
+                .into());
+            }
+        }
+
+        // leaf ids
+        let n = leaves.len();
+        let mut ids: Vec<usize> = vec![0; nodes.len()];
+        let labels: Vec<String> = match leaf_order {
+            Some(order) => {
+                if order.len() != n {
+                    return Err(format!("Expected {} labels but got {}", n, order.len()).into());
+                }
+                let positions: HashMap<&str, usize> = order
+                    .iter()
+                    .enumerate()
+                    .map(|(i, l)| (l.as_str(), i))
+                    .collect();
+                if positions.len() != n {
+                    return Err("Leaf labels must be unique".into());
+                }
+                let mut seen = vec![false; n];
+                for &leaf in &leaves {
+                    let id = *positions.get(nodes[leaf].label.as_str()).ok_or_else(|| {
+                        format!("Leaf '{}' is not in the label list", nodes[leaf].label)
+                    })?;
+                    if seen[id] {
+                        return Err(
+                            format!("Leaf '{}' appears more than once", nodes[leaf].label).into(),
+                        );
+                    }
+                    seen[id] = true;
+                    ids[leaf] = id;
+                }
+                order.to_vec()
+            }
+            None => {
+                for (id, &leaf) in leaves.iter().enumerate() {
+                    ids[leaf] = id;
+                }
+                leaves
+                    .iter()
+                    .map(|&leaf| nodes[leaf].label.clone())
+                    .collect()
+            }
+        };
+
+        // heights from the leaves, children are never above their parent
+        let mut height = vec![0.0; nodes.len()];
+        for idx in (0..nodes.len()).rev() {
+            if !nodes[idx].children.is_empty() {
+                let child_max = nodes[idx]
+                    .children
+                    .iter()
+                    .map(|&c| height[c])
+                    .fold(0.0, f64::max);
+                height[idx] = (tree_height - depth[idx]).max(child_max);
+            }
+        }
+
+        // merge in height order, children (later in pre-order) before parents on ties
+        let mut internal: Vec<usize> = (0..nodes.len())
+            .filter(|&i| !nodes[i].children.is_empty())
+            .collect();
+        internal.sort_by(|&a, &b| height[a].total_cmp(&height[b]).then(b.cmp(&a)));
+        for (rank, &idx) in internal.iter().enumerate() {
+            ids[idx] = n + rank;
+        }
+
+        let linkage: Vec<[f64; 4]> = internal
+            .iter()
+            .map(|&idx| {
+                let children = &nodes[idx].children;
+                [
+                    ids[children[0]] as f64,
+                    ids[children[1]] as f64,
+                    height[idx],
+                    0.0,
+                ]
+            })
+            .collect();
+
+        let mut hierarchy = ClusterHierarchy::from_linkage_matrix(&linkage)?;
+        hierarchy.set_labels(labels)?;
+
+        return Ok(hierarchy);
+    }
+
+    /// Loads a Newick file, see `from_newick`
+    pub fn load_newick(
+        fname: &str,
+        leaf_order: Option<&[String]>,
+    ) -> Result<ClusterHierarchy, Box<dyn Error>> {
+        let newick = std::fs::read_to_string(fname)?;
+        return ClusterHierarchy::from_newick(&newick, leaf_order);
+    }
+}
+
+/// Parses a Newick string into a flat list of nodes in pre-order
+fn parse_newick(newick: &str) -> Result<Vec<NewickNode>, Box<dyn Error>> {
+    let chars: Vec<char> = newick.chars().collect();
+    let mut nodes: Vec<NewickNode> = Vec::new();
+    let mut open: Vec<usize> = Vec::new();
+    // the node a following label or branch length belongs to
+    let mut current: Option<usize> = None;
+    // true right after '(' or ',' where a child (possibly unnamed) is expected
+    let mut expect_child = true;
+    let mut pos = 0;
+
+    while pos < chars.len() {
+        let c = chars[pos];
+        match c {
+            c if c.is_whitespace() => pos += 1,
+            '[' => {
+                let end = chars[pos..]
+                    .iter()
+                    .position(|&c| c == ']')
+                    .ok_or("Unterminated Newick comment")?;
+                pos += end + 1;
+            }
+            '(' => {
+                if !expect_child {
+                    return Err(format!("Unexpected '(' at position {}", pos).into());
+                }
+                let idx = push_node(&mut nodes, &open);
+                open.push(idx);
+                current = None;
+                pos += 1;
+            }
+            ',' | ')' => {
+                if expect_child {
+                    // unnamed leaf
+                    push_node(&mut nodes, &open);
+                }
+                if open.is_empty() {
+                    return Err(format!("Unbalanced '{}' at position {}", c, pos).into());
+                }
+                if c == ')' {
+                    current = open.pop();
+                    expect_child = false;
+                } else {
+                    current = None;
+                    expect_child = true;
+                }
+                pos += 1;
+            }
+            ':' => {
+                let idx = match current {
+                    Some(idx) if !expect_child => idx,
+                    _ => push_node(&mut nodes, &open),
+                };
+                let mut start = pos + 1;
+                while start < chars.len() && chars[start].is_whitespace() {
+                    start += 1;
+                }
+                let end = chars[start..]
+                    .iter()
+                    .position(|&c| matches!(c, ',' | ')' | ';' | '[') || c.is_whitespace())
+                    .map(|p| start + p)
+                    .unwrap_or(chars.len());
+                let num: String = chars[start..end].iter().collect();
+                let length = num
+                    .parse::<f64>()
+                    .map_err(|_| format!("Invalid branch length '{}' at position {}", num, pos))?;
+                nodes[idx].length = Some(length);
+                current = Some(idx);
+                expect_child = false;
+                pos = end;
+            }
+            ';' => {
+                if !open.is_empty() {
+                    return Err("Unbalanced parentheses in Newick string".into());
+                }
+                if expect_child && nodes.is_empty() {
+                    push_node(&mut nodes, &open);
+                }
+                break;
+            }
+            _ => {
+                let (label, end) = read_label(&chars, pos)?;
+                let idx = match current {
+                    Some(idx) if !expect_child => idx,
+                    _ => push_node(&mut nodes, &open),
+                };
+                nodes[idx].label = label;
+                current = Some(idx);
+                expect_child = false;
+                pos = end;
+            }
+        }
+    }
+
+    if !open.is_empty() {
+        return Err("Unbalanced parentheses in Newick string".into());
+    }
+    if nodes.is_empty() {
+        return Err("Empty Newick string".into());
+    }
+
+    return Ok(nodes);
+}
+
+/// Appends a node as the last child of the innermost open node
+fn push_node(nodes: &mut Vec<NewickNode>, open: &[usize]) -> usize {
+    let idx = nodes.len();
+    let parent = open.last().copied();
+    nodes.push(NewickNode {
+        label: String::new(),
+        length: None,
+        parent,
+        children: vec![],
+    });
+    if let Some(parent) = parent {
+        nodes[parent].children.push(idx);
+    }
+
+    return idx;
+}
+
+/// Reads a quoted or unquoted label starting at `pos`, returns it with the end position
+///
+/// As in the Newick standard, an underscore in an unquoted label stands for a space.
+fn read_label(chars: &[char], pos: usize) -> Result<(String, usize), Box<dyn Error>> {
+    if chars[pos] != '\'' {
+        let end = chars[pos..]
+            .iter()
+            .position(|&c| {
+                c.is_whitespace() || matches!(c, '(' | ')' | '[' | ']' | '\'' | ':' | ';' | ',')
+            })
+            .map(|p| pos + p)
+            .unwrap_or(chars.len());
+        let label = chars[pos..end]
+            .iter()
+            .map(|&c| if c == '_' { ' ' } else { c })
+            .collect();
+        return Ok((label, end));
+    }
+
+    let mut label = String::new();
+    let mut idx = pos + 1;
+    while idx < chars.len() {
+        if chars[idx] == '\'' {
+            // a doubled quote is an escaped quote
+            if chars.get(idx + 1) == Some(&'\'') {
+                label.push('\'');
+                idx += 2;
+                continue;
+            }
+            return Ok((label, idx + 1));
+        }
+        label.push(chars[idx]);
+        idx += 1;
+    }
+
+    return Err(format!("Unterminated quoted label at position {}", pos).into());
+}
+
+/// Quotes a label when it contains whitespace or Newick metacharacters
+fn quote_label(label: &str) -> String {
+    if label.is_empty()
+        || label
+            .chars()
+            .any(|c| c.is_whitespace() || NEWICK_META.contains(&c))
+    {
+        return format!("'{}'", label.replace('\'', "''"));
+    }
+
+    return label.to_string();
+}
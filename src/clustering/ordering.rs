@@ -0,0 +1,245 @@
+use std::error::Error;
+
+use serde::{Deserialize, Serialize};
+
+use super::{ClusterHierarchy, ClusterMap, Merge};
+
+/// How leaf weights are combined into a subtree weight when reordering
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum WeightAggregation {
+    Mean,
+    Sum,
+    Max,
+}
+
+impl ClusterHierarchy {
+    /// Returns a copy of the hierarchy with an optimal leaf ordering (Bar-Joseph et al. 2001)
+    ///
+    /// The children of every merge are flipped so that the sum of the distances between
+    /// adjacent leaves is minimised. The topology and the merge heights are left untouched,
+    /// only the left/right order of the children changes.
+    ///
+    /// ### params:
+    /// - dist_mat: the distance matrix the hierarchy was built from (`n x n`)
+    pub fn optimal_leaf_ordering(
+        &self,
+        dist_mat: &[Vec<f64>],
+    ) -> Result<ClusterHierarchy, Box<dyn Error>> {
+        let n = self.original_n;
+        if dist_mat.len() != n || dist_mat.iter().any(|row| row.len() != n) {
+            return Err(
+                format!("Distance matrix must be {}x{} to match the hierarchy", n, n).into(),
+            );
+        }
+
+        if n < 3 {
+            return Ok(self.clone());
+        }
+
+        let cluster_map = leaf_map(&self.merges, n);
+        let merge_idx = merge_lookup(&self.merges, n);
+
+        // cost[i][j]: cheapest ordering of the subtree lca(i, j) that starts at i and ends at j
+        let mut cost = vec![vec![0.0f64; n]; n];
+        // split[i][j]: the two leaves meeting at the junction of the children for that ordering
+        let mut split = vec![vec![(0usize, 0usize); n]; n];
+
+        let mut best_to = vec![0.0; n];
+        let mut best_from = vec![0usize; n];
+
+        for merge in &self.merges {
+            let left = cluster_map.get_cluster(merge.cid1);
+            let right = cluster_map.get_cluster(merge.cid2);
+
+            // lower bound on any junction distance, used to prune the inner loops
+            let min_junction = left
+                .iter()
+                .flat_map(|&k| right.iter().map(move |&m| dist_mat[k][m]))
+                .fold(f64::MAX, f64::min);
+
+            for &i in left {
+                let mut inner_left =
+                    inner_leaves(merge.cid1, i, n, &self.merges, &merge_idx, &cluster_map).to_vec();
+                inner_left.sort_by(|&a, &b| cost[i][a].total_cmp(&cost[i][b]));
+
+                for &m in right {
+                    let mut best = f64::MAX;
+                    let mut arg = inner_left[0];
+                    for &k in &inner_left {
+                        if cost[i][k] + min_junction >= best {
+                            break;
+                        }
+                        let curr = cost[i][k] + dist_mat[k][m];
+                        if curr < best {
+                            best = curr;
+                            arg = k;
+                        }
+                    }
+                    best_to[m] = best;
+                    best_from[m] = arg;
+                }
+
+                for &j in right {
+                    let inner_right =
+                        inner_leaves(merge.cid2, j, n, &self.merges, &merge_idx, &cluster_map);
+
+                    let mut best = f64::MAX;
+                    let mut arg = inner_right[0];
+                    for &m in inner_right {
+                        let curr = best_to[m] + cost[m][j];
+                        if curr < best {
+                            best = curr;
+                            arg = m;
+                        }
+                    }
+
+                    cost[i][j] = best;
+                    cost[j][i] = best;
+                    split[i][j] = (best_from[arg], arg);
+                    split[j][i] = (arg, best_from[arg]);
+                }
+            }
+        }
+
+        // pick the cheapest pair of outer leaves for the root
+        let root = self.merges.last().unwrap();
+        let mut start = 0;
+        let mut end = 0;
+        let mut best = f64::MAX;
+        for &i in cluster_map.get_cluster(root.cid1) {
+            for &j in cluster_map.get_cluster(root.cid2) {
+                if cost[i][j] < best {
+                    best = cost[i][j];
+                    start = i;
+                    end = j;
+                }
+            }
+        }
+
+        let mut merges: Vec<(usize, usize, f64, usize)> = self
+            .merges
+            .iter()
+            .map(|m| (m.cid1, m.cid2, m.dist, m.new_cid))
+            .collect();
+
+        let mut stack: Vec<(usize, usize, usize)> = vec![(root.new_cid, start, end)];
+        while let Some((cid, start, end)) = stack.pop() {
+            if cid < n {
+                continue;
+            }
+
+            let idx = merge_idx[cid];
+            let (cid1, cid2, _, _) = merges[idx];
+            let (first, second) = if cluster_map.get_cluster(cid1).contains(&start) {
+                (cid1, cid2)
+            } else {
+                (cid2, cid1)
+            };
+            merges[idx].0 = first;
+            merges[idx].1 = second;
+
+            let (k, m) = split[start][end];
+            stack.push((first, start, k));
+            stack.push((second, m, end));
+        }
+
+        return Ok(ClusterHierarchy::new(&merges, n).with_labels(self.labels.clone()));
+    }
+
+    /// Returns a copy of the hierarchy with children ordered by aggregated leaf weights
+    ///
+    /// Works like R's `reorder.dendrogram`: at every merge the child with the lower
+    /// aggregated weight is placed first (left). Ties keep the current order.
+    ///
+    /// ### params:
+    /// - weights: one weight per leaf, indexed by leaf id
+    /// - agg: `WeightAggregation` (Enum) used to combine the weights of a subtree
+    pub fn reorder_by_weights(
+        &self,
+        weights: &[f64],
+        agg: WeightAggregation,
+    ) -> Result<ClusterHierarchy, Box<dyn Error>> {
+        let n = self.original_n;
+        if weights.len() != n {
+            return Err(format!("Expected {} leaf weights but got {}", n, weights.len()).into());
+        }
+
+        // (sum, count, max) for every cluster id
+        let mut stats: Vec<(f64, f64, f64)> = weights.iter().map(|&w| (w, 1.0, w)).collect();
+        stats.resize(n + self.merges.len(), (0.0, 0.0, f64::MIN));
+
+        let aggregate = |(sum, count, max): (f64, f64, f64)| match agg {
+            WeightAggregation::Mean => sum / count,
+            WeightAggregation::Sum => sum,
+            WeightAggregation::Max => max,
+        };
+
+        let mut merges: Vec<(usize, usize, f64, usize)> = Vec::with_capacity(self.merges.len());
+        for merge in &self.merges {
+            let s1 = stats[merge.cid1];
+            let s2 = stats[merge.cid2];
+
+            if merge.new_cid >= stats.len() {
+                stats.resize(merge.new_cid + 1, (0.0, 0.0, f64::MIN));
+            }
+            stats[merge.new_cid] = (s1.0 + s2.0, s1.1 + s2.1, s1.2.max(s2.2));
+
+            if aggregate(s1) > aggregate(s2) {
+                merges.push((merge.cid2, merge.cid1, merge.dist, merge.new_cid));
+            } else {
+                merges.push((merge.cid1, merge.cid2, merge.dist, merge.new_cid));
+            }
+        }
+
+        return Ok(ClusterHierarchy::new(&merges, n).with_labels(self.labels.clone()));
+    }
+}
+
+/// Maps every cluster id to the leaves it contains
+pub(in crate::clustering) fn leaf_map(merges: &[Merge], n: usize) -> ClusterMap {
+    let mut cluster_map = ClusterMap::new(n);
+    for merge in merges {
+        let mut merged_cids = Vec::new();
+        merged_cids.extend_from_slice(cluster_map.get_cluster(merge.cid1));
+        merged_cids.extend_from_slice(cluster_map.get_cluster(merge.cid2));
+
+        if merge.new_cid >= cluster_map.len() {
+            cluster_map.add_new_cid(merge.new_cid + 1);
+        }
+        cluster_map.replace_cid_vals(merge.new_cid, &merged_cids);
+    }
+
+    return cluster_map;
+}
+
+/// Maps every cluster id to the index of the merge that created it
+pub(in crate::clustering) fn merge_lookup(merges: &[Merge], n: usize) -> Vec<usize> {
+    let max_cid = merges.iter().map(|m| m.new_cid).max().unwrap_or(0);
+    let mut lookup = vec![usize::MAX; (max_cid + 1).max(n)];
+    for (idx, merge) in merges.iter().enumerate() {
+        lookup[merge.new_cid] = idx;
+    }
+
+    return lookup;
+}
+
+/// Leaves of `cid` that can sit next to the junction when the subtree starts at `leaf`
+fn inner_leaves<'a>(
+    cid: usize,
+    leaf: usize,
+    n: usize,
+    merges: &[Merge],
+    merge_idx: &[usize],
+    cluster_map: &'a ClusterMap,
+) -> &'a [usize] {
+    if cid < n {
+        return cluster_map.get_cluster(cid);
+    }
+
+    let merge = &merges[merge_idx[cid]];
+    if cluster_map.get_cluster(merge.cid1).contains(&leaf) {
+        return cluster_map.get_cluster(merge.cid2);
+    } else {
+        return cluster_map.get_cluster(merge.cid1);
+    }
+}
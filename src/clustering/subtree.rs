@@ -0,0 +1,85 @@
+use std::error::Error;
+
+use super::ClusterHierarchy;
+
+impl ClusterHierarchy {
+    /// # Extracts the subtree rooted at `node` as a standalone hierarchy
+    ///
+    /// The leaves below `node` are renumbered `0..k` in ascending order of their original ids
+    /// and keep their merge heights. The new hierarchy is labelled with the original labels, or
+    /// with the original leaf ids when this hierarchy is unlabelled.
+    ///
+    /// ### params:
+    /// - node: cluster id of the subtree root (a merge `new_cid`, see `TreeView`)
+    pub fn subtree(&self, node: usize) -> Result<ClusterHierarchy, Box<dyn Error>> {
+        let is_node = node < self.original_n || self.merges.iter().any(|m| m.new_cid == node);
+        if !is_node {
+            return Err(format!("Node {} is not in the tree", node).into());
+        }
+
+        let view = self.tree_view();
+        let mut keep = vec![false; self.original_n];
+        for &leaf in view.leaves(node) {
+            keep[leaf] = true;
+        }
+
+        return self.restrict(&keep);
+    }
+
+    /// # Removes leaves from the hierarchy
+    ///
+    /// Merges that lose one side are dropped and the remaining side takes their place, so the
+    /// topology and merge heights of the kept leaves are unchanged. Kept leaves are renumbered
+    /// `0..m` in ascending order of their original ids and labelled like in `subtree`.
+    ///
+    /// ### params:
+    /// - leaves: ids of the leaves to drop (e.g. control wells)
+    pub fn prune(&self, leaves: &[usize]) -> Result<ClusterHierarchy, Box<dyn Error>> {
+        let mut keep = vec![true; self.original_n];
+        for &leaf in leaves {
+            if leaf >= self.original_n {
+                return Err(format!(
+                    "Leaf {} is out of range for {} leaves",
+                    leaf, self.original_n
+                )
+                .into());
+            }
+            keep[leaf] = false;
+        }
+
+        return self.restrict(&keep);
+    }
+
+    /// Hierarchy over the leaves flagged in `keep`
+    fn restrict(&self, keep: &[bool]) -> Result<ClusterHierarchy, Box<dyn Error>> {
+        let kept: Vec<usize> = (0..self.original_n).filter(|&leaf| keep[leaf]).collect();
+        let m = kept.len();
+        if m < 2 {
+            return Err(format!("At least 2 leaves must remain but {} would", m).into());
+        }
+
+        // new id of every cluster that still has leaves
+        let max_cid = self.merges.iter().map(|m| m.new_cid).max().unwrap_or(0);
+        let mut new_ids: Vec<Option<usize>> = vec![None; (max_cid + 1).max(self.original_n)];
+        for (new_id, &leaf) in kept.iter().enumerate() {
+            new_ids[leaf] = Some(new_id);
+        }
+
+        let mut merges: Vec<(usize, usize, f64, usize)> = Vec::with_capacity(m - 1);
+        for merge in &self.merges {
+            new_ids[merge.new_cid] = match (new_ids[merge.cid1], new_ids[merge.cid2]) {
+                (Some(cid1), Some(cid2)) => {
+                    let new_cid = m + merges.len();
+                    merges.push((cid1, cid2, merge.dist, new_cid));
+                    Some(new_cid)
+                }
+                (Some(cid), None) | (None, Some(cid)) => Some(cid),
+                (None, None) => None,
+            };
+        }
+
+        let labels: Vec<String> = kept.iter().map(|&leaf| self.leaf_label(leaf)).collect();
+
+        return Ok(ClusterHierarchy::new(&merges, m).with_labels(Some(labels)));
+    }
+}
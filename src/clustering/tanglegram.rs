@@ -0,0 +1,269 @@
+use std::{collections::HashMap, error::Error, fs::File, io::Write};
+
+use serde::{Deserialize, Serialize};
+
+use super::{utils::csv_field, ClusterHierarchy};
+
+/// How `untangle` rotates the two hierarchies
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum UntangleMethod {
+    /// rotate the left tree only, the right tree keeps its leaf order
+    OneSide,
+    /// rotate both trees in turn until the number of crossings stops dropping
+    TwoSide,
+}
+
+/// Two hierarchies drawn facing each other with lines between their shared leaves
+///
+/// Built with `Tanglegram::new` (leaf orders as given) or `untangle` (rotated to reduce the
+/// crossing lines). Rotating only swaps the children of merges, so heights and clusters are
+/// unchanged.
+#[derive(Debug, Clone)]
+pub struct Tanglegram {
+    pub left: ClusterHierarchy,
+    pub right: ClusterHierarchy,
+    /// `(left leaf, right leaf)` of every shared leaf
+    pub links: Vec<(usize, usize)>,
+}
+
+/// # Rotates two hierarchies to minimise the crossing lines of their tanglegram
+///
+/// Like the step-wise heuristics of R `dendextend::untangle`. With the other tree fixed, a
+/// merge can be flipped on its own: it only changes whether the pairs of leaves across its two
+/// children cross. `OneSide` flips every merge of the left tree that reduces the crossings,
+/// which is optimal for the fixed right leaf order. `TwoSide` alternates between the trees
+/// until a round brings no improvement.
+///
+/// ### params:
+/// - left, right: the hierarchies, leaves are matched by label when both are labelled and by
+///   leaf id otherwise
+/// - method: `UntangleMethod`
+pub fn untangle(
+    left: &ClusterHierarchy,
+    right: &ClusterHierarchy,
+    method: UntangleMethod,
+) -> Result<Tanglegram, Box<dyn Error>> {
+    let mut tangle = Tanglegram::new(left, right)?;
+    let flipped: Vec<(usize, usize)> = tangle.links.iter().map(|&(l, r)| (r, l)).collect();
+
+    let mut crossings = tangle.crossings();
+    loop {
+        tangle.left = rotate_against(&tangle.left, &tangle.right, &tangle.links);
+        if method == UntangleMethod::TwoSide {
+            tangle.right = rotate_against(&tangle.right, &tangle.left, &flipped);
+        }
+
+        let new_crossings = tangle.crossings();
+        if method == UntangleMethod::OneSide || new_crossings >= crossings {
+            break;
+        }
+        crossings = new_crossings;
+    }
+
+    return Ok(tangle);
+}
+
+impl Tanglegram {
+    /// # Pairs two hierarchies without rotating them
+    ///
+    /// Leaves are linked by label when both hierarchies are labelled (leaves found in only one
+    /// tree get no line) and by leaf id otherwise, which needs the same number of leaves.
+    pub fn new(
+        left: &ClusterHierarchy,
+        right: &ClusterHierarchy,
+    ) -> Result<Tanglegram, Box<dyn Error>> {
+        let links: Vec<(usize, usize)> = match (left.labels(), right.labels()) {
+            (Some(left_labels), Some(right_labels)) => {
+                let right_ids = label_ids(right_labels)?;
+                // only checks that the left labels are unique
+                label_ids(left_labels)?;
+                left_labels
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(leaf, label)| Some((leaf, *right_ids.get(label.as_str())?)))
+                    .collect()
+            }
+            _ => {
+                if left.leaf_size() != right.leaf_size() {
+                    return Err(format!(
+                        "Unlabelled hierarchies need the same leaves but have {} and {}",
+                        left.leaf_size(),
+                        right.leaf_size()
+                    )
+                    .into());
+                }
+                (0..left.leaf_size()).map(|leaf| (leaf, leaf)).collect()
+            }
+        };
+        if links.is_empty() {
+            return Err("The hierarchies share no leaves".into());
+        }
+
+        return Ok(Tanglegram {
+            left: left.clone(),
+            right: right.clone(),
+            links,
+        });
+    }
+
+    /// Number of pairs of lines that cross
+    pub fn crossings(&self) -> usize {
+        let left_pos = positions(&self.left);
+        let right_pos = positions(&self.right);
+
+        let mut links: Vec<(usize, usize)> = self
+            .links
+            .iter()
+            .map(|&(l, r)| (left_pos[l], right_pos[r]))
+            .collect();
+        links.sort();
+        let mut right_order: Vec<usize> = links.iter().map(|&(_, r)| r).collect();
+
+        return count_inversions(&mut right_order);
+    }
+
+    /// Leaf ids of the left tree from top to bottom
+    pub fn left_order(&self) -> Vec<usize> {
+        return self.left.leaf_ordering();
+    }
+
+    /// Leaf ids of the right tree from top to bottom
+    pub fn right_order(&self) -> Vec<usize> {
+        return self.right.leaf_ordering();
+    }
+
+    /// Writes both leaf orders side by side as csv with a
+    /// `position,left_leaf,left_label,right_leaf,right_label` header
+    pub fn write_orders(&self, fname: &str) -> Result<(), Box<dyn Error>> {
+        let left = self.left_order();
+        let right = self.right_order();
+
+        let mut csv = String::from("position,left_leaf,left_label,right_leaf,right_label\n");
+        for pos in 0..left.len().max(right.len()) {
+            let side = |order: &[usize], hierarchy: &ClusterHierarchy| match order.get(pos) {
+                Some(&leaf) => format!("{},{}", leaf, csv_field(&hierarchy.leaf_label(leaf))),
+                None => ",".to_string(),
+            };
+            csv.push_str(&format!(
+                "{},{},{}\n",
+                pos,
+                side(&left, &self.left),
+                side(&right, &self.right)
+            ));
+        }
+
+        let mut file = File::create(fname)?;
+        file.write_all(csv.as_bytes())?;
+
+        return Ok(());
+    }
+}
+
+/// # Flips the merges of `hierarchy` that reduce crossings with the fixed `other` tree
+///
+/// Bottom-up, every cluster keeps the sorted positions in `other` of its linked leaves. Merging
+/// the two children counts the crossing pairs like a merge sort counts inversions; when more
+/// than half of the pairs across the children cross, swapping the children uncrosses them.
+fn rotate_against(
+    hierarchy: &ClusterHierarchy,
+    other: &ClusterHierarchy,
+    links: &[(usize, usize)],
+) -> ClusterHierarchy {
+    if hierarchy.merges.is_empty() {
+        return hierarchy.clone();
+    }
+
+    let other_pos = positions(other);
+    let max_cid = hierarchy
+        .merges
+        .iter()
+        .map(|m| m.new_cid)
+        .max()
+        .unwrap_or(0);
+    let mut sorted: Vec<Vec<usize>> = vec![vec![]; (max_cid + 1).max(hierarchy.original_n)];
+    for &(leaf, other_leaf) in links {
+        sorted[leaf].push(other_pos[other_leaf]);
+    }
+
+    let mut merges: Vec<(usize, usize, f64, usize)> = Vec::with_capacity(hierarchy.merges.len());
+    for merge in &hierarchy.merges {
+        let first = std::mem::take(&mut sorted[merge.cid1]);
+        let second = std::mem::take(&mut sorted[merge.cid2]);
+
+        let mut merged = Vec::with_capacity(first.len() + second.len());
+        let mut crossing = 0;
+        let (mut i, mut j) = (0, 0);
+        while i < first.len() || j < second.len() {
+            if j == second.len() || (i < first.len() && first[i] <= second[j]) {
+                merged.push(first[i]);
+                i += 1;
+            } else {
+                // every remaining leaf of the first child is below this one in `other`
+                crossing += first.len() - i;
+                merged.push(second[j]);
+                j += 1;
+            }
+        }
+        sorted[merge.new_cid] = merged;
+
+        match 2 * crossing > first.len() * second.len() {
+            true => merges.push((merge.cid2, merge.cid1, merge.dist, merge.new_cid)),
+            false => merges.push((merge.cid1, merge.cid2, merge.dist, merge.new_cid)),
+        }
+    }
+
+    return ClusterHierarchy::new(&merges, hierarchy.original_n)
+        .with_labels(hierarchy.labels.clone());
+}
+
+/// Position of every leaf in the leaf order
+fn positions(hierarchy: &ClusterHierarchy) -> Vec<usize> {
+    let mut pos = vec![0; hierarchy.original_n];
+    for (i, leaf) in hierarchy.leaf_ordering().into_iter().enumerate() {
+        pos[leaf] = i;
+    }
+
+    return pos;
+}
+
+/// Leaf id of every label, labels must be unique
+fn label_ids(labels: &[String]) -> Result<HashMap<&str, usize>, Box<dyn Error>> {
+    let mut ids: HashMap<&str, usize> = HashMap::with_capacity(labels.len());
+    for (id, label) in labels.iter().enumerate() {
+        if ids.insert(label, id).is_some() {
+            return Err(format!("Duplicate leaf label '{}'", label).into());
+        }
+    }
+
+    return Ok(ids);
+}
+
+/// Number of pairs `i < j` with `values[i] > values[j]`, sorts `values` (bottom-up merge sort)
+fn count_inversions(values: &mut [usize]) -> usize {
+    let mut inversions = 0;
+    let mut buffer = values.to_vec();
+
+    let mut width = 1;
+    while width < values.len() {
+        for start in (0..values.len()).step_by(2 * width) {
+            let mid = (start + width).min(values.len());
+            let end = (start + 2 * width).min(values.len());
+            let (mut i, mut j, mut k) = (start, mid, start);
+            while i < mid || j < end {
+                if j == end || (i < mid && values[i] <= values[j]) {
+                    buffer[k] = values[i];
+                    i += 1;
+                } else {
+                    inversions += mid - i;
+                    buffer[k] = values[j];
+                    j += 1;
+                }
+                k += 1;
+            }
+        }
+        values.copy_from_slice(&buffer);
+        width *= 2;
+    }
+
+    return inversions;
+}
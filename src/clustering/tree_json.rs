@@ -0,0 +1,304 @@
+use std::error::Error;
+
+use super::DendrogramNode;
+
+/// A piece of output still to be written by `write_json_tree`
+enum Step<'a> {
+    /// a node and its depth
+    Node(&'a DendrogramNode, usize),
+    Text(String),
+}
+
+/// A json tree node whose fields are still being read by `parse_json_tree`
+#[derive(Default)]
+struct PartialNode {
+    cid: Option<usize>,
+    distance: Option<f64>,
+    size: usize,
+    label: Option<String>,
+    left: Option<DendrogramNode>,
+    right: Option<DendrogramNode>,
+    /// `left` or `right` while that child object is being read
+    open_child: Option<String>,
+    has_fields: bool,
+}
+
+/// # Writes the tree in the json form of `ClusterHierarchy::to_json_tree`
+///
+/// The output is identical to `serde_json::to_string_pretty` (or `serde_json::to_string` when
+/// `pretty` is false) of the node but is written with an explicit stack, so single linkage
+/// chains with many thousands of levels don't overflow. Pretty output indents every level, so
+/// its size grows quadratically with the depth of a chain.
+pub(in crate::clustering) fn write_json_tree<'a>(root: &'a DendrogramNode, pretty: bool) -> String {
+    let mut json = String::new();
+    let (colon, newline) = match pretty {
+        true => (": ", "\n"),
+        false => (":", ""),
+    };
+    let indent = |depth: usize| match pretty {
+        true => "  ".repeat(depth),
+        false => String::new(),
+    };
+
+    let mut stack = vec![Step::Node(root, 0)];
+    while let Some(step) = stack.pop() {
+        let (node, depth) = match step {
+            Step::Text(text) => {
+                json.push_str(&text);
+                continue;
+            }
+            Step::Node(node, depth) => (node, depth),
+        };
+
+        let field = |name: &str| format!("{}{}\"{}\"{}", newline, indent(depth + 1), name, colon);
+        json.push('{');
+        json.push_str(&format!("{}{},", field("cid"), node.cid));
+        json.push_str(&format!(
+            "{}{},",
+            field("distance"),
+            json_number(node.distance)
+        ));
+        json.push_str(&format!("{}{},", field("size"), node.size));
+        if let Some(label) = &node.label {
+            json.push_str(&format!("{}{},", field("label"), json_string(label)));
+        }
+
+        // children are written by later steps, leaves get `null`
+        let child = |child: &'a Option<Box<DendrogramNode>>| match child {
+            Some(child) => Step::Node(child, depth + 1),
+            None => Step::Text("null".to_string()),
+        };
+        json.push_str(&field("left"));
+        stack.push(Step::Text(format!("{}{}}}", newline, indent(depth))));
+        stack.push(child(&node.right));
+        stack.push(Step::Text(format!(",{}", field("right"))));
+        stack.push(child(&node.left));
+    }
+
+    return json;
+}
+
+/// # Reads a json tree written by `write_json_tree` (or by serde)
+///
+/// Objects are read with an explicit stack of partially built nodes instead of recursive
+/// descent, so the depth of the tree is only limited by memory. Unknown fields are skipped and
+/// `size`/`label` may be missing, like the derived `Deserialize` of `DendrogramNode`.
+pub(in crate::clustering) fn parse_json_tree(json: &str) -> Result<DendrogramNode, Box<dyn Error>> {
+    let mut reader = Reader {
+        bytes: json.as_bytes(),
+        json,
+        pos: 0,
+    };
+
+    reader.expect(b'{')?;
+    let mut stack = vec![PartialNode::default()];
+    loop {
+        let token = reader.next_byte()?;
+        if token == b'}' {
+            let node = stack.pop().unwrap().finish()?;
+            match stack.last_mut() {
+                Some(parent) => {
+                    match parent.open_child.take().as_deref() {
+                        Some("left") => parent.left = Some(node),
+                        _ => parent.right = Some(node),
+                    }
+                    continue;
+                }
+                None => {
+                    if reader.peek().is_some() {
+                        return Err(reader.error("Trailing characters after the tree"));
+                    }
+                    return Ok(node);
+                }
+            }
+        }
+
+        let top = stack.last_mut().unwrap();
+        if top.has_fields {
+            if token != b',' {
+                return Err(reader.error("Expected ',' or '}'"));
+            }
+            reader.expect(b'"')?;
+        } else if token != b'"' {
+            return Err(reader.error("Expected a field name"));
+        }
+        top.has_fields = true;
+
+        let key = reader.string_body()?;
+        reader.expect(b':')?;
+        match key.as_str() {
+            "cid" => top.cid = Some(reader.number()? as usize),
+            "size" => top.size = reader.number()? as usize,
+            "distance" => {
+                top.distance = Some(match reader.null()? {
+                    true => f64::NAN,
+                    false => reader.number()?,
+                })
+            }
+            "label" => {
+                top.label = match reader.null()? {
+                    true => None,
+                    false => {
+                        reader.expect(b'"')?;
+                        Some(reader.string_body()?)
+                    }
+                }
+            }
+            "left" | "right" => {
+                if !reader.null()? {
+                    reader.expect(b'{')?;
+                    top.open_child = Some(key);
+                    stack.push(PartialNode::default());
+                }
+            }
+            _ => reader.skip_value()?,
+        }
+    }
+}
+
+impl PartialNode {
+    fn finish(self) -> Result<DendrogramNode, Box<dyn Error>> {
+        let cid = self.cid.ok_or("Tree node is missing its cid")?;
+        let distance = self
+            .distance
+            .ok_or(format!("Tree node {} is missing its distance", cid))?;
+
+        let mut node = DendrogramNode::new(
+            cid,
+            distance,
+            self.size,
+            self.left.map(Box::new),
+            self.right.map(Box::new),
+        );
+        node.label = self.label;
+
+        return Ok(node);
+    }
+}
+
+/// Byte cursor over the json text
+struct Reader<'a> {
+    bytes: &'a [u8],
+    json: &'a str,
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn error(&self, msg: &str) -> Box<dyn Error> {
+        return format!("{} at position {}", msg, self.pos).into();
+    }
+
+    /// Next non-whitespace byte without consuming it
+    fn peek(&mut self) -> Option<u8> {
+        while self.pos < self.bytes.len() && self.bytes[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+        return self.bytes.get(self.pos).copied();
+    }
+
+    fn next_byte(&mut self) -> Result<u8, Box<dyn Error>> {
+        let byte = self
+            .peek()
+            .ok_or_else(|| self.error("Unexpected end of json"))?;
+        self.pos += 1;
+
+        return Ok(byte);
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), Box<dyn Error>> {
+        if self.next_byte()? != byte {
+            self.pos -= 1;
+            return Err(self.error(&format!("Expected '{}'", byte as char)));
+        }
+
+        return Ok(());
+    }
+
+    /// Consumes `null` if it comes next
+    fn null(&mut self) -> Result<bool, Box<dyn Error>> {
+        if self.peek() == Some(b'n') {
+            if !self.bytes[self.pos..].starts_with(b"null") {
+                return Err(self.error("Invalid literal"));
+            }
+            self.pos += 4;
+            return Ok(true);
+        }
+
+        return Ok(false);
+    }
+
+    /// Reads the rest of a string whose opening quote was consumed, escapes are decoded by
+    /// serde_json
+    fn string_body(&mut self) -> Result<String, Box<dyn Error>> {
+        let start = self.pos - 1;
+        while self.pos < self.bytes.len() {
+            match self.bytes[self.pos] {
+                b'\\' => self.pos += 2,
+                b'"' => {
+                    self.pos += 1;
+                    return Ok(serde_json::from_str(&self.json[start..self.pos])?);
+                }
+                _ => self.pos += 1,
+            }
+        }
+
+        return Err(self.error("Unterminated string"));
+    }
+
+    fn number(&mut self) -> Result<f64, Box<dyn Error>> {
+        self.peek();
+        let start = self.pos;
+        while self.pos < self.bytes.len()
+            && matches!(
+                self.bytes[self.pos],
+                b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E'
+            )
+        {
+            self.pos += 1;
+        }
+
+        return self.json[start..self.pos]
+            .parse()
+            .map_err(|_| self.error("Expected a number"));
+    }
+
+    /// Skips a value of an unknown field, nested containers are skipped by counting brackets
+    fn skip_value(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut depth = 0usize;
+        loop {
+            match self.next_byte()? {
+                b'"' => {
+                    self.string_body()?;
+                }
+                b'{' | b'[' => depth += 1,
+                b'}' | b']' => {
+                    depth = depth
+                        .checked_sub(1)
+                        .ok_or_else(|| self.error("Unbalanced brackets"))?;
+                }
+                _ => {
+                    // scalar: consume up to the next delimiter
+                    while self.pos < self.bytes.len()
+                        && !matches!(self.bytes[self.pos], b',' | b'}' | b']')
+                        && !self.bytes[self.pos].is_ascii_whitespace()
+                    {
+                        self.pos += 1;
+                    }
+                }
+            }
+
+            if depth == 0 {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Formats a float the way serde_json does (`null` for non finite values)
+pub(in crate::clustering) fn json_number(value: f64) -> String {
+    return serde_json::to_string(&value).unwrap();
+}
+
+pub(in crate::clustering) fn json_string(text: &str) -> String {
+    return serde_json::to_string(text).unwrap();
+}
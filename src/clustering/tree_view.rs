@@ -0,0 +1,215 @@
+use std::collections::VecDeque;
+
+use super::ClusterHierarchy;
+
+/// # Index based view of a `ClusterHierarchy` tree
+///
+/// Nodes are identified by their cluster id: leaves are `0..n` and every merge node is the
+/// `new_cid` of its merge. All nodes live in flat arrays indexed by that id, so parent,
+/// children, depth, height, size and subtree leaf queries are O(1). Lowest common ancestors
+/// use a heavy path decomposition and take O(log n). Built with `ClusterHierarchy::tree_view`.
+#[derive(Debug, Clone)]
+pub struct TreeView {
+    root: usize,
+    num_leaves: usize,
+    parent: Vec<Option<usize>>,
+    children: Vec<Option<(usize, usize)>>,
+    height: Vec<f64>,
+    size: Vec<usize>,
+    depth: Vec<usize>,
+    /// top node of the heavy path a node belongs to
+    head: Vec<usize>,
+    /// leaves in leaf order, every subtree covers `leaf_order[leaf_start..leaf_end]`
+    leaf_order: Vec<usize>,
+    leaf_start: Vec<usize>,
+    leaf_end: Vec<usize>,
+    preorder: Vec<usize>,
+    postorder: Vec<usize>,
+}
+
+impl ClusterHierarchy {
+    /// Builds the index based `TreeView` of the hierarchy in O(n)
+    pub fn tree_view(&self) -> TreeView {
+        let n = self.original_n;
+        let max_cid = self.merges.iter().map(|m| m.new_cid).max().unwrap_or(0);
+        let len = (max_cid + 1).max(n);
+
+        let mut parent: Vec<Option<usize>> = vec![None; len];
+        let mut children: Vec<Option<(usize, usize)>> = vec![None; len];
+        let mut height = vec![0.0; len];
+        let mut size = vec![0; len];
+        size[..n].fill(1);
+        for merge in &self.merges {
+            parent[merge.cid1] = Some(merge.new_cid);
+            parent[merge.cid2] = Some(merge.new_cid);
+            children[merge.new_cid] = Some((merge.cid1, merge.cid2));
+            height[merge.new_cid] = merge.dist;
+            size[merge.new_cid] = size[merge.cid1] + size[merge.cid2];
+        }
+        let root = self.merges.last().map(|m| m.new_cid).unwrap_or(0);
+
+        let mut view = TreeView {
+            root,
+            num_leaves: n,
+            parent,
+            children,
+            height,
+            size,
+            depth: vec![0; len],
+            head: (0..len).collect(),
+            leaf_order: Vec::with_capacity(n),
+            leaf_start: vec![0; len],
+            leaf_end: vec![0; len],
+            preorder: Vec::with_capacity(2 * n),
+            postorder: Vec::with_capacity(2 * n),
+        };
+        if n == 0 {
+            return view;
+        }
+
+        // depth first walk: (node, number of children already visited)
+        let mut stack: Vec<(usize, usize)> = vec![(root, 0)];
+        while let Some((node, visited)) = stack.pop() {
+            if visited == 0 {
+                view.preorder.push(node);
+                view.leaf_start[node] = view.leaf_order.len();
+                if view.children[node].is_none() {
+                    view.leaf_order.push(node);
+                }
+            }
+
+            match view.children[node] {
+                Some((left, right)) if visited < 2 => {
+                    let child = if visited == 0 { left } else { right };
+                    let sibling = if visited == 0 { right } else { left };
+                    view.depth[child] = view.depth[node] + 1;
+                    // the larger child continues the heavy path (left on ties)
+                    let heavy = view.size[child] > view.size[sibling]
+                        || (view.size[child] == view.size[sibling] && child == left);
+                    if heavy {
+                        view.head[child] = view.head[node];
+                    }
+                    stack.push((node, visited + 1));
+                    stack.push((child, 0));
+                }
+                _ => {
+                    view.leaf_end[node] = view.leaf_order.len();
+                    view.postorder.push(node);
+                }
+            }
+        }
+
+        return view;
+    }
+}
+
+impl TreeView {
+    /// Id of the root node
+    pub fn root(&self) -> usize {
+        self.root
+    }
+
+    /// Number of leaves (`n`), the nodes `0..n`
+    pub fn num_leaves(&self) -> usize {
+        self.num_leaves
+    }
+
+    /// Number of nodes in the tree (`2n - 1`)
+    pub fn num_nodes(&self) -> usize {
+        self.preorder.len()
+    }
+
+    pub fn is_leaf(&self, node: usize) -> bool {
+        self.children[node].is_none()
+    }
+
+    /// Parent of `node`, `None` for the root
+    pub fn parent(&self, node: usize) -> Option<usize> {
+        self.parent[node]
+    }
+
+    /// `(left, right)` children of `node`, `None` for leaves
+    pub fn children(&self, node: usize) -> Option<(usize, usize)> {
+        self.children[node]
+    }
+
+    /// Number of edges between `node` and the root
+    pub fn depth(&self, node: usize) -> usize {
+        self.depth[node]
+    }
+
+    /// Merge height of `node` (0 for leaves)
+    pub fn height(&self, node: usize) -> f64 {
+        self.height[node]
+    }
+
+    /// Number of leaves below `node`
+    pub fn size(&self, node: usize) -> usize {
+        self.size[node]
+    }
+
+    /// Leaves below `node` in leaf order
+    pub fn leaves(&self, node: usize) -> &[usize] {
+        &self.leaf_order[self.leaf_start[node]..self.leaf_end[node]]
+    }
+
+    /// All leaves in leaf order (same as `ClusterHierarchy::leaf_ordering`)
+    pub fn leaf_order(&self) -> &[usize] {
+        &self.leaf_order
+    }
+
+    /// Whether `ancestor` is `node` or lies on the path from `node` to the root
+    ///
+    /// Subtrees cover nested or disjoint ranges of the leaf order, so this is a range check.
+    pub fn is_ancestor(&self, ancestor: usize, node: usize) -> bool {
+        return self.leaf_start[ancestor] <= self.leaf_start[node]
+            && self.leaf_end[node] <= self.leaf_end[ancestor];
+    }
+
+    /// # Lowest common ancestor of two nodes
+    ///
+    /// Climbs heavy paths until both nodes are on the same one, which takes O(log n) steps.
+    /// The height of the lowest common ancestor of two leaves is their cophenetic distance.
+    pub fn lca(&self, a: usize, b: usize) -> usize {
+        let (mut a, mut b) = (a, b);
+        while self.head[a] != self.head[b] {
+            if self.depth[self.head[a]] > self.depth[self.head[b]] {
+                a = self.parent[self.head[a]].unwrap();
+            } else {
+                b = self.parent[self.head[b]].unwrap();
+            }
+        }
+
+        if self.depth[a] <= self.depth[b] {
+            return a;
+        }
+        return b;
+    }
+
+    /// Nodes in pre-order (parent, left subtree, right subtree)
+    pub fn preorder(&self) -> impl Iterator<Item = usize> + '_ {
+        self.preorder.iter().copied()
+    }
+
+    /// Nodes in post-order (left subtree, right subtree, parent)
+    pub fn postorder(&self) -> impl Iterator<Item = usize> + '_ {
+        self.postorder.iter().copied()
+    }
+
+    /// Nodes in level order (breadth first from the root, left before right)
+    pub fn level_order(&self) -> impl Iterator<Item = usize> + '_ {
+        let mut queue: VecDeque<usize> = VecDeque::new();
+        if !self.preorder.is_empty() {
+            queue.push_back(self.root);
+        }
+
+        return std::iter::from_fn(move || {
+            let node = queue.pop_front()?;
+            if let Some((left, right)) = self.children[node] {
+                queue.push_back(left);
+                queue.push_back(right);
+            }
+            return Some(node);
+        });
+    }
+}
@@ -1,18 +1,36 @@
-use std::{collections::HashMap, error::Error, fs::File, io::Write};
+use std::{collections::HashMap, error::Error, fmt, fs::File, io::Write};
 
-use serde::{Deserialize, Serialize};
+use serde::{
+    de::{Error as _, IgnoredAny, MapAccess, Visitor},
+    ser::SerializeStruct,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
 
-use super::{ClusterHierarchy, Merge};
+use super::{
+    tree_json::{parse_json_tree, write_json_tree},
+    ClusterHierarchy, Merge,
+};
 
 impl ClusterHierarchy {
     pub(in crate::clustering) fn new(merges: &[(usize, usize, f64, usize)], n: usize) -> Self {
+        // number of leaves under every cluster id
+        let max_cid = merges.iter().map(|m| m.3).max().unwrap_or(0);
+        let mut sizes = vec![0; (max_cid + 1).max(n)];
+        sizes[..n].fill(1);
+
         let merge_vec: Vec<Merge> = merges
             .iter()
-            .map(|&(cid1, cid2, dist, new_cid)| Merge {
-                cid1,
-                cid2,
-                dist,
-                new_cid,
+            .map(|&(cid1, cid2, dist, new_cid)| {
+                let size = sizes[cid1] + sizes[cid2];
+                sizes[new_cid] = size;
+
+                Merge {
+                    cid1,
+                    cid2,
+                    dist,
+                    new_cid,
+                    size,
+                }
             })
             .collect();
 
@@ -20,6 +38,7 @@ impl ClusterHierarchy {
             merges: merge_vec,
             original_n: n,
             tree: None,
+            labels: None,
         };
 
         let tree = build_tree(&cluster);
@@ -29,15 +48,69 @@ impl ClusterHierarchy {
         return cluster;
     }
 
+    /// Returns the same hierarchy with `labels` attached (used when deriving hierarchies)
+    pub(in crate::clustering) fn with_labels(mut self, labels: Option<Vec<String>>) -> Self {
+        self.labels = labels;
+        self.label_tree_leaves();
+
+        return self;
+    }
+
+    /// Copies the labels onto the leaves of the built tree in place
+    fn label_tree_leaves(&mut self) {
+        let labels = &self.labels;
+        let mut stack: Vec<&mut DendrogramNode> = self.tree.iter_mut().collect();
+        while let Some(node) = stack.pop() {
+            if node.left.is_none() && node.right.is_none() {
+                node.label = labels.as_ref().map(|labels| labels[node.cid].clone());
+            }
+            stack.extend(node.left.as_deref_mut());
+            stack.extend(node.right.as_deref_mut());
+        }
+    }
+
     /// gives original input items
     pub fn leaf_size(&self) -> usize {
         self.original_n
     }
 
+    /// Attaches leaf labels (indexed by leaf id) to the hierarchy
+    ///
+    /// Labels are emitted by the json tree, the merge list and the leaf order outputs.
+    pub fn set_labels(&mut self, labels: Vec<String>) -> Result<(), Box<dyn Error>> {
+        if labels.len() != self.original_n {
+            return Err(format!(
+                "Expected {} labels but got {}",
+                self.original_n,
+                labels.len()
+            )
+            .into());
+        }
+
+        self.labels = Some(labels);
+        self.label_tree_leaves();
+
+        return Ok(());
+    }
+
+    /// Returns the leaf labels if the hierarchy is labelled
+    pub fn labels(&self) -> Option<&[String]> {
+        self.labels.as_deref()
+    }
+
+    /// Returns the label of a leaf, or its index when the hierarchy is unlabelled
+    pub fn leaf_label(&self, leaf: usize) -> String {
+        match &self.labels {
+            Some(labels) => labels[leaf].clone(),
+            None => leaf.to_string(),
+        }
+    }
+
     /// This saves the raw merge list as a json file. No trees are involved here
+    ///
+    /// Labelled hierarchies are written as `{"labels": [...], "merges": [...]}`
     pub fn simple_save(&self, filename: &str) -> std::io::Result<()> {
-        let json_str =
-            serde_json::to_string_pretty(&self.merges).expect("Can't serialize hierarchy!");
+        let json_str = self.to_string().expect("Can't serialize hierarchy!");
 
         let mut file = File::create(filename)?;
         file.write_all(json_str.as_bytes())?;
@@ -47,7 +120,15 @@ impl ClusterHierarchy {
 
     /// returns the merge list as a json formatted string
     pub fn to_string(&self) -> Result<String, serde_json::Error> {
-        return Ok(serde_json::to_string_pretty(&self.merges).expect("Can't serialize hierarchy!"));
+        let json_str = match &self.labels {
+            Some(labels) => serde_json::to_string_pretty(&LabeledMerges {
+                labels: labels.clone(),
+                merges: self.merges.clone(),
+            }),
+            None => serde_json::to_string_pretty(&self.merges),
+        };
+
+        return Ok(json_str.expect("Can't serialize hierarchy!"));
     }
 
     /// Returns the cluster outputin json tree form
@@ -57,13 +138,27 @@ impl ClusterHierarchy {
     /// - DendrogramNode:
     ///     - cid: cluster id as a usize var
     ///     - dist: the distance between 2 nodes as a f64 float
+    ///     - size: the number of leaves below the node as a usize var
+    ///     - label: the leaf label (only on leaves of labelled hierarchies)
     ///     - left: the Left child as a DendrogramNode or None if it is a leaf
     ///     - right: the Right child as a DendrogramNode or None if it is a leaf
+    ///
+    /// The json is pretty printed without recursion. Indentation grows with the depth of the
+    /// tree, use `to_compact_json_tree` for deep single linkage chains.
     pub fn to_json_tree(&self) -> Result<String, Box<dyn Error>> {
         if let Some(ref root) = &self.tree {
-            let json_str = serde_json::to_string_pretty(&root).expect("Couldn't build json tree!");
+            return Ok(write_json_tree(root, true));
+        } else {
+            return Err("No Tree found!".into());
+        }
+    }
 
-            return Ok(json_str);
+    /// Returns the json tree of `to_json_tree` without whitespace
+    ///
+    /// The size is linear in the number of nodes however deep the tree is.
+    pub fn to_compact_json_tree(&self) -> Result<String, Box<dyn Error>> {
+        if let Some(ref root) = &self.tree {
+            return Ok(write_json_tree(root, false));
         } else {
             return Err("No Tree found!".into());
         }
@@ -83,15 +178,156 @@ impl ClusterHierarchy {
         return Err("Couldn't retrieve json tree".into());
     }
 
+    /// Writes the compact json tree of `to_compact_json_tree` to a file
+    pub fn write_compact_tree(&self, fname: &str) -> Result<(), Box<dyn Error>> {
+        let json = self.to_compact_json_tree()?;
+
+        let mut file = File::create(fname)?;
+        file.write_all(json.as_bytes())?;
+
+        return Ok(());
+    }
+
+    /// Loads a hierarchy from a merge list written by `simple_save`
+    ///
+    /// The merge list is validated (ids, monotonic distances, single root) and the
+    /// dendrogram tree is rebuilt from it.
+    pub fn load_merges(fname: &str) -> Result<ClusterHierarchy, Box<dyn Error>> {
+        let json_str = std::fs::read_to_string(fname)?;
+        let (merges, labels) = match serde_json::from_str(&json_str)? {
+            SavedMerges::Plain(merges) => (merges, None),
+            SavedMerges::Labeled(saved) => (saved.merges, Some(saved.labels)),
+        };
+
+        return ClusterHierarchy::from_merges(&merges, labels);
+    }
+
+    /// Validates a merge list and rebuilds the hierarchy (and its tree) from it
+    pub(in crate::clustering) fn from_merges(
+        merges: &[Merge],
+        labels: Option<Vec<String>>,
+    ) -> Result<ClusterHierarchy, Box<dyn Error>> {
+        let n = merges.len() + 1;
+        let mut linkage: Vec<[f64; 4]> = Vec::with_capacity(merges.len());
+        for (i, merge) in merges.iter().enumerate() {
+            if merge.new_cid != n + i {
+                return Err(format!(
+                    "Merge {}: expected new cid {} but found {}",
+                    i,
+                    n + i,
+                    merge.new_cid
+                )
+                .into());
+            }
+            linkage.push([
+                merge.cid1 as f64,
+                merge.cid2 as f64,
+                merge.dist,
+                merge.size as f64,
+            ]);
+        }
+
+        let mut hierarchy = ClusterHierarchy::from_linkage_matrix(&linkage)?;
+        if let Some(labels) = labels {
+            hierarchy.set_labels(labels)?;
+        }
+
+        return Ok(hierarchy);
+    }
+
+    /// Loads a hierarchy from a json tree written by `write_tree`
+    ///
+    /// Leaves must use the ids `0..n`. Internal nodes are renumbered `n..2n - 1` in
+    /// ascending cid order, which is the merge order for trees written by this crate.
+    pub fn load_tree(fname: &str) -> Result<ClusterHierarchy, Box<dyn Error>> {
+        let json_str = std::fs::read_to_string(fname)?;
+        let root = parse_json_tree(&json_str)?;
+
+        let mut leaves: Vec<&DendrogramNode> = Vec::new();
+        let mut internal: Vec<&DendrogramNode> = Vec::new();
+        collect_nodes(&root, &mut leaves, &mut internal)?;
+
+        let n = leaves.len();
+        let mut leaf_labels: Vec<Option<String>> = vec![None; n];
+        for leaf in &leaves {
+            if leaf.cid < n {
+                leaf_labels[leaf.cid] = leaf.label.clone();
+            }
+        }
+
+        let mut leaves: Vec<usize> = leaves.iter().map(|leaf| leaf.cid).collect();
+        leaves.sort();
+        if leaves.iter().enumerate().any(|(i, &cid)| i != cid) {
+            return Err(format!("Tree leaves must be numbered 0..{}", n).into());
+        }
+
+        internal.sort_by_key(|node| node.cid);
+        let mut new_ids: HashMap<usize, usize> = HashMap::new();
+        for (i, node) in internal.iter().enumerate() {
+            if node.cid < n || new_ids.insert(node.cid, n + i).is_some() {
+                return Err(format!("Invalid or duplicate internal node id {}", node.cid).into());
+            }
+        }
+        let new_id = |cid: usize| -> f64 {
+            if cid < n {
+                return cid as f64;
+            }
+            return new_ids[&cid] as f64;
+        };
+
+        let linkage: Vec<[f64; 4]> = internal
+            .iter()
+            .map(|node| {
+                let left = node.left.as_ref().unwrap();
+                let right = node.right.as_ref().unwrap();
+                [new_id(left.cid), new_id(right.cid), node.distance, 0.0]
+            })
+            .collect();
+
+        let mut hierarchy = ClusterHierarchy::from_linkage_matrix(&linkage)?;
+        if leaf_labels.iter().all(|label| label.is_some()) {
+            hierarchy.set_labels(leaf_labels.into_iter().flatten().collect())?;
+        }
+
+        return Ok(hierarchy);
+    }
+
     /// Returns the leaf ordering which can be used to reorder heatmaps
     pub fn leaf_ordering(&self) -> Vec<usize> {
         if let Some(ref tree) = &self.tree {
-            return get_leaf_order(&tree);
+            return get_leaf_order(tree);
         } else {
             return vec![];
         }
     }
 
+    /// Returns the leaf labels in leaf order (leaf indices when unlabelled)
+    pub fn leaf_label_ordering(&self) -> Vec<String> {
+        return self
+            .leaf_ordering()
+            .iter()
+            .map(|&leaf| self.leaf_label(leaf))
+            .collect();
+    }
+
+    /// Writes the leaf ordering as csv with a `position,leaf,label` header
+    pub fn write_leaf_order(&self, fname: &str) -> Result<(), Box<dyn Error>> {
+        let mut csv = String::from("position,leaf,label\n");
+        for (pos, leaf) in self.leaf_ordering().iter().enumerate() {
+            csv.push_str(&format!(
+                "{},{},{}\n",
+                pos,
+                leaf,
+                csv_field(&self.leaf_label(*leaf))
+            ));
+        }
+
+        let mut file = File::create(fname)?;
+        file.write_all(csv.as_bytes())?;
+
+        return Ok(());
+    }
+
     /// Returns a copy of the Dendrogram nodes
     pub fn get_raw_nodes(&self) -> Option<DendrogramNode> {
         self.tree.clone()
@@ -99,10 +335,18 @@ impl ClusterHierarchy {
 }
 
 /// A node used to represent the dendrogram tree
-#[derive(Debug, Serialize, Deserialize, Clone)]
+///
+/// Cloning, dropping and formatting with `{:?}` walk the tree with an explicit stack, and
+/// (de)serialising grows the stack on the heap as it nests, so the deep chains produced by
+/// single linkage are safe. Serialised to json a node reads like
+/// `ClusterHierarchy::to_compact_json_tree`, which writes the same text much faster.
 pub struct DendrogramNode {
     pub cid: usize,
     pub distance: f64,
+    /// number of leaves below this node (1 for a leaf)
+    pub size: usize,
+    /// leaf label for labelled hierarchies
+    pub label: Option<String>,
     pub left: Option<Box<DendrogramNode>>,
     pub right: Option<Box<DendrogramNode>>,
 }
@@ -111,18 +355,187 @@ impl DendrogramNode {
     pub(in crate::clustering) fn new(
         cid: usize,
         dist: f64,
+        size: usize,
         left: Option<Box<DendrogramNode>>,
         right: Option<Box<DendrogramNode>>,
     ) -> Self {
         DendrogramNode {
             cid,
             distance: dist,
+            size,
+            label: None,
             left,
             right,
         }
     }
 }
 
+impl Clone for DendrogramNode {
+    fn clone(&self) -> Self {
+        // pre-order (node, left subtree, right subtree), rebuilt in reverse so both children
+        // are on the stack when their parent comes up, the left one on top
+        let mut order: Vec<&DendrogramNode> = Vec::new();
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            order.push(node);
+            stack.extend(node.right.as_deref());
+            stack.extend(node.left.as_deref());
+        }
+
+        let mut built: Vec<DendrogramNode> = Vec::with_capacity(order.len());
+        for node in order.into_iter().rev() {
+            let left = node.left.as_ref().map(|_| Box::new(built.pop().unwrap()));
+            let right = node.right.as_ref().map(|_| Box::new(built.pop().unwrap()));
+            let mut copy = DendrogramNode::new(node.cid, node.distance, node.size, left, right);
+            copy.label = node.label.clone();
+            built.push(copy);
+        }
+
+        return built.pop().unwrap();
+    }
+}
+
+/// Free stack below which a nested (de)serialisation step moves to a fresh stack segment
+const STACK_RED_ZONE: usize = 64 * 1024;
+/// Size of every extra stack segment
+const STACK_SEGMENT: usize = 1024 * 1024;
+
+/// Fields of a serialised `DendrogramNode`
+const NODE_FIELDS: [&str; 6] = ["cid", "distance", "size", "label", "left", "right"];
+
+impl Serialize for DendrogramNode {
+    /// Same struct as a derived impl, so any serde format works
+    ///
+    /// Nested formats need one serializer call per tree level, so every level checks the
+    /// remaining stack and continues on a heap allocated segment when it runs low.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT, || {
+            let len = if self.label.is_some() { 6 } else { 5 };
+            let mut node = serializer.serialize_struct("DendrogramNode", len)?;
+            node.serialize_field("cid", &self.cid)?;
+            node.serialize_field("distance", &self.distance)?;
+            node.serialize_field("size", &self.size)?;
+            match &self.label {
+                Some(label) => node.serialize_field("label", label)?,
+                None => node.skip_field("label")?,
+            }
+            node.serialize_field("left", &self.left)?;
+            node.serialize_field("right", &self.right)?;
+
+            return node.end();
+        });
+    }
+}
+
+impl<'de> Deserialize<'de> for DendrogramNode {
+    /// Reads the struct written by `Serialize`, with the stack growth of `serialize`
+    ///
+    /// Like the json tree reader, `size` and `label` may be missing, a null `distance` is NaN
+    /// and unknown fields are skipped.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        return stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT, || {
+            deserializer.deserialize_struct("DendrogramNode", &NODE_FIELDS, NodeVisitor)
+        });
+    }
+}
+
+struct NodeVisitor;
+
+impl<'de> Visitor<'de> for NodeVisitor {
+    type Value = DendrogramNode;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return f.write_str("a dendrogram node");
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<DendrogramNode, A::Error> {
+        let mut cid: Option<usize> = None;
+        let mut distance: Option<Option<f64>> = None;
+        let mut size = 0;
+        let mut label: Option<String> = None;
+        let mut left: Option<Box<DendrogramNode>> = None;
+        let mut right: Option<Box<DendrogramNode>> = None;
+
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "cid" => cid = Some(map.next_value()?),
+                "distance" => distance = Some(map.next_value()?),
+                "size" => size = map.next_value()?,
+                "label" => label = map.next_value()?,
+                "left" => left = map.next_value()?,
+                "right" => right = map.next_value()?,
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        let cid = cid.ok_or_else(|| A::Error::missing_field("cid"))?;
+        let distance = distance.ok_or_else(|| A::Error::missing_field("distance"))?;
+        let mut node = DendrogramNode::new(cid, distance.unwrap_or(f64::NAN), size, left, right);
+        node.label = label;
+
+        return Ok(node);
+    }
+}
+
+/// A piece of output still to be written by the `Debug` impl of `DendrogramNode`
+enum DebugStep<'a> {
+    Node(&'a DendrogramNode),
+    Text(&'static str),
+}
+
+impl fmt::Debug for DendrogramNode {
+    /// Same text as a derived `Debug` without the `{:#?}` line breaks
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut stack = vec![DebugStep::Node(self)];
+        while let Some(step) = stack.pop() {
+            let node = match step {
+                DebugStep::Text(text) => {
+                    f.write_str(text)?;
+                    continue;
+                }
+                DebugStep::Node(node) => node,
+            };
+
+            write!(
+                f,
+                "DendrogramNode {{ cid: {:?}, distance: {:?}, size: {:?}, label: {:?}, left: ",
+                node.cid, node.distance, node.size, node.label
+            )?;
+            stack.push(DebugStep::Text(" }"));
+            for (i, child) in [&node.right, &node.left].into_iter().enumerate() {
+                match child {
+                    Some(child) => {
+                        stack.push(DebugStep::Text(")"));
+                        stack.push(DebugStep::Node(child));
+                        stack.push(DebugStep::Text("Some("));
+                    }
+                    None => stack.push(DebugStep::Text("None")),
+                }
+                if i == 0 {
+                    stack.push(DebugStep::Text(", right: "));
+                }
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+impl Drop for DendrogramNode {
+    fn drop(&mut self) {
+        // detach the children first so no node drops a subtree recursively
+        let mut stack: Vec<Box<DendrogramNode>> = Vec::new();
+        stack.extend(self.left.take());
+        stack.extend(self.right.take());
+        while let Some(mut node) = stack.pop() {
+            stack.extend(node.left.take());
+            stack.extend(node.right.take());
+        }
+    }
+}
+
 /// Builds a dendrogram tree
 pub(in crate::clustering) fn build_tree(cluster: &ClusterHierarchy) -> DendrogramNode {
     // We know that the last item in merge list is the root
@@ -130,8 +543,10 @@ pub(in crate::clustering) fn build_tree(cluster: &ClusterHierarchy) -> Dendrogra
     let mut nodes: HashMap<usize, DendrogramNode> = HashMap::new();
 
     // create leaf nodes
-    for i in 0..cluster.original_n.clone() {
-        nodes.insert(i, DendrogramNode::new(i, 0.0, None, None));
+    for i in 0..cluster.original_n {
+        let mut leaf = DendrogramNode::new(i, 0.0, 1, None, None);
+        leaf.label = cluster.labels.as_ref().map(|labels| labels[i].clone());
+        nodes.insert(i, leaf);
     }
 
     for &merge in &cluster.merges {
@@ -139,17 +554,19 @@ pub(in crate::clustering) fn build_tree(cluster: &ClusterHierarchy) -> Dendrogra
         let cid2 = merge.cid2;
         let dist = merge.dist;
         let new_cid = merge.new_cid;
+        let size = merge.size;
 
         let left_node = nodes
             .remove(&cid1)
-            .expect(format!("Can't find left node of {} in node list!", cid1).as_str());
+            .unwrap_or_else(|| panic!("Can't find left node of {} in node list!", cid1));
         let right_node = nodes
             .remove(&cid2)
-            .expect(format!("Can't find right node of {} in node list!", cid2).as_str());
+            .unwrap_or_else(|| panic!("Can't find right node of {} in node list!", cid2));
 
         let new_node = DendrogramNode::new(
             new_cid,
             dist,
+            size,
             Some(Box::new(left_node)),
             Some(Box::new(right_node)),
         );
@@ -162,19 +579,64 @@ pub(in crate::clustering) fn build_tree(cluster: &ClusterHierarchy) -> Dendrogra
     return root_node;
 }
 
-/// Grabs Tree Leaf ordering
-fn get_leaf_order(root: &DendrogramNode) -> Vec<usize> {
-    if root.left.is_none() && root.right.is_none() {
-        return vec![root.cid];
+/// On-disk form of `simple_save` for labelled hierarchies
+#[derive(Serialize, Deserialize)]
+struct LabeledMerges {
+    labels: Vec<String>,
+    merges: Vec<Merge>,
+}
+
+/// Either form written by `simple_save`
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedMerges {
+    Plain(Vec<Merge>),
+    Labeled(LabeledMerges),
+}
+
+/// Quotes a csv field when needed
+pub(in crate::clustering) fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        return format!("\"{}\"", field.replace('"', "\"\""));
     }
 
-    let mut orders: Vec<usize> = Vec::new();
+    return field.to_string();
+}
 
-    if let Some(ref left) = root.left {
-        orders.extend(get_leaf_order(left));
+/// Splits the tree into leaves and internal nodes
+fn collect_nodes<'a>(
+    root: &'a DendrogramNode,
+    leaves: &mut Vec<&'a DendrogramNode>,
+    internal: &mut Vec<&'a DendrogramNode>,
+) -> Result<(), Box<dyn Error>> {
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        match (&node.left, &node.right) {
+            (None, None) => leaves.push(node),
+            (Some(left), Some(right)) => {
+                internal.push(node);
+                stack.push(right);
+                stack.push(left);
+            }
+            _ => return Err(format!("Node {} has only one child", node.cid).into()),
+        }
     }
-    if let Some(ref right) = root.right {
-        orders.extend(get_leaf_order(right));
+
+    return Ok(());
+}
+
+/// Grabs Tree Leaf ordering
+fn get_leaf_order(root: &DendrogramNode) -> Vec<usize> {
+    let mut orders: Vec<usize> = Vec::new();
+
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        if node.left.is_none() && node.right.is_none() {
+            orders.push(node.cid);
+            continue;
+        }
+        stack.extend(node.right.as_deref());
+        stack.extend(node.left.as_deref());
     }
 
     return orders;
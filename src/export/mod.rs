@@ -0,0 +1,5 @@
+mod report;
+mod treeview;
+
+pub use report::{html_report, write_html_report, ReportOptions};
+pub use treeview::write_treeview;
@@ -0,0 +1,159 @@
+use std::{error::Error, fs::File, io::Write};
+
+use serde::Serialize;
+
+use crate::{
+    plot::{
+        clustermap::{check_inputs, resolve_labels},
+        svg,
+    },
+    Annotation, ClusterHierarchy, ColorMap,
+};
+
+const TEMPLATE: &str = include_str!("report.html");
+
+/// Options for `html_report`
+#[derive(Debug, Clone)]
+pub struct ReportOptions {
+    pub title: String,
+    pub colormap: ColorMap,
+    /// row names, defaulting to the row hierarchy labels and then to the row index
+    pub row_labels: Option<Vec<String>>,
+    /// column names, defaulting to the column hierarchy labels and then to the column index
+    pub col_labels: Option<Vec<String>>,
+    /// per row metadata shown as color bars and in the tooltips
+    pub row_metadata: Vec<Annotation>,
+    /// per column metadata shown as color bars and in the tooltips
+    pub col_metadata: Vec<Annotation>,
+}
+
+impl Default for ReportOptions {
+    fn default() -> Self {
+        ReportOptions {
+            title: "Clustered heatmap".to_string(),
+            colormap: ColorMap::default(),
+            row_labels: None,
+            col_labels: None,
+            row_metadata: vec![],
+            col_metadata: vec![],
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ReportAxis<'a> {
+    labels: Vec<String>,
+    linkage: Vec<[f64; 4]>,
+    meta: &'a [Annotation],
+}
+
+#[derive(Serialize)]
+struct ReportColors {
+    low: [u8; 3],
+    mid: [u8; 3],
+    high: [u8; 3],
+    nan: [u8; 3],
+    center: f64,
+    vmin: f64,
+    vmax: f64,
+}
+
+#[derive(Serialize)]
+struct ReportData<'a> {
+    data: &'a [Vec<f64>],
+    rows: ReportAxis<'a>,
+    cols: ReportAxis<'a>,
+    cmap: ReportColors,
+}
+
+/// # Builds a self-contained interactive html report of a clustered heatmap
+///
+/// The page works offline (the data and the script are embedded, no CDN). It draws the
+/// heatmap with both dendrograms and supports zooming, collapsing dendrogram nodes (collapsed
+/// rows/columns show the mean), searching the labels and tooltips with the per leaf metadata.
+///
+/// ### params:
+/// - data: `&[Vec<f64>]` the clustered data matrix (rows x features), NaN is shown as missing
+/// - row_hierarchy: `ClusterHierarchy` of the rows
+/// - col_hierarchy: optional `ClusterHierarchy` of the columns
+/// - options: `ReportOptions` with the title, colormap, labels and metadata
+pub fn html_report(
+    data: &[Vec<f64>],
+    row_hierarchy: &ClusterHierarchy,
+    col_hierarchy: Option<&ClusterHierarchy>,
+    options: &ReportOptions,
+) -> Result<String, Box<dyn Error>> {
+    let (nrows, ncols) = check_inputs(
+        data,
+        row_hierarchy,
+        col_hierarchy,
+        &options.row_metadata,
+        &options.col_metadata,
+    )?;
+
+    let cmap = &options.colormap;
+    let (vmin, vmax) = cmap.range(data);
+    let payload = ReportData {
+        data,
+        rows: ReportAxis {
+            labels: resolve_labels(
+                options.row_labels.as_deref(),
+                Some(row_hierarchy),
+                nrows,
+                "row",
+            )?,
+            linkage: row_hierarchy.linkage_matrix(),
+            meta: &options.row_metadata,
+        },
+        cols: ReportAxis {
+            labels: resolve_labels(
+                options.col_labels.as_deref(),
+                col_hierarchy,
+                ncols,
+                "column",
+            )?,
+            linkage: col_hierarchy
+                .map(|cols| cols.linkage_matrix())
+                .unwrap_or_default(),
+            meta: &options.col_metadata,
+        },
+        cmap: ReportColors {
+            low: cmap.low,
+            mid: cmap.mid,
+            high: cmap.high,
+            nan: cmap.nan_color,
+            center: cmap.center,
+            vmin,
+            vmax,
+        },
+    };
+
+    // `<` only occurs inside json strings, escaping it keeps `</script>` out of the page
+    let json = serde_json::to_string(&payload)?.replace('<', "\\u003c");
+
+    let title = svg::escape(&options.title);
+    let (head, tail) = TEMPLATE.split_once("__DATA__").unwrap();
+
+    return Ok(format!(
+        "{}{}{}",
+        head.replace("__TITLE__", &title),
+        json,
+        tail.replace("__TITLE__", &title)
+    ));
+}
+
+/// Writes the html report to `fname`, see `html_report`
+pub fn write_html_report(
+    fname: &str,
+    data: &[Vec<f64>],
+    row_hierarchy: &ClusterHierarchy,
+    col_hierarchy: Option<&ClusterHierarchy>,
+    options: &ReportOptions,
+) -> Result<(), Box<dyn Error>> {
+    let html = html_report(data, row_hierarchy, col_hierarchy, options)?;
+
+    let mut file = File::create(fname)?;
+    file.write_all(html.as_bytes())?;
+
+    return Ok(());
+}
@@ -0,0 +1,160 @@
+use std::{error::Error, fs::File, io::Write};
+
+use crate::ClusterHierarchy;
+
+/// # Writes a Java TreeView / Cluster 3.0 file trio
+///
+/// Creates `<prefix>.cdt` with the data reordered by the clustering, `<prefix>.gtr` with the
+/// row tree and, when columns were clustered, `<prefix>.atr` with the column tree. Rows are
+/// named `GENE<i>X`, columns `ARRY<i>X` and internal nodes `NODE<i>X` like Cluster 3.0.
+/// Node scores are written correlation style as `1 - distance`.
+///
+/// ### params:
+/// - prefix: output path without extension
+/// - data: `&[Vec<f64>]` the clustered data matrix (rows x features), NaN is written as missing
+/// - row_hierarchy: `ClusterHierarchy` of the rows
+/// - col_hierarchy: optional `ClusterHierarchy` of the columns
+/// - row_labels / col_labels: optional names, defaulting to the hierarchy labels and then to
+///   the row/column index
+pub fn write_treeview(
+    prefix: &str,
+    data: &[Vec<f64>],
+    row_hierarchy: &ClusterHierarchy,
+    col_hierarchy: Option<&ClusterHierarchy>,
+    row_labels: Option<&[String]>,
+    col_labels: Option<&[String]>,
+) -> Result<(), Box<dyn Error>> {
+    let nrows = data.len();
+    let ncols = data.first().map(|row| row.len()).unwrap_or(0);
+
+    if data.iter().any(|row| row.len() != ncols) {
+        return Err("All rows of the data matrix must have the same length".into());
+    }
+    if row_hierarchy.leaf_size() != nrows {
+        return Err(format!(
+            "Row hierarchy has {} leaves but the data has {} rows",
+            row_hierarchy.leaf_size(),
+            nrows
+        )
+        .into());
+    }
+    if let Some(cols) = col_hierarchy {
+        if cols.leaf_size() != ncols {
+            return Err(format!(
+                "Column hierarchy has {} leaves but the data has {} columns",
+                cols.leaf_size(),
+                ncols
+            )
+            .into());
+        }
+    }
+
+    let row_names = resolve_labels(row_labels.or(row_hierarchy.labels()), nrows, "row")?;
+    let col_names = resolve_labels(
+        col_labels.or(col_hierarchy.and_then(|cols| cols.labels())),
+        ncols,
+        "column",
+    )?;
+
+    let row_order = row_hierarchy.leaf_ordering();
+    let col_order = match col_hierarchy {
+        Some(cols) => cols.leaf_ordering(),
+        None => (0..ncols).collect(),
+    };
+
+    // .cdt
+    let mut cdt = String::from("GID\tUNIQID\tNAME\tGWEIGHT");
+    for &c in &col_order {
+        cdt.push_str(&format!("\t{}", col_names[c]));
+    }
+    cdt.push('\n');
+
+    if col_hierarchy.is_some() {
+        cdt.push_str("AID\t\t\t");
+        for &c in &col_order {
+            cdt.push_str(&format!("\tARRY{}X", c));
+        }
+        cdt.push('\n');
+    }
+
+    cdt.push_str("EWEIGHT\t\t\t");
+    cdt.push_str(&"\t1".repeat(ncols));
+    cdt.push('\n');
+
+    for &r in &row_order {
+        cdt.push_str(&format!(
+            "GENE{}X\t{}\t{}\t1",
+            r, row_names[r], row_names[r]
+        ));
+        for &c in &col_order {
+            let val = data[r][c];
+            match val.is_nan() {
+                true => cdt.push('\t'),
+                false => cdt.push_str(&format!("\t{}", val)),
+            }
+        }
+        cdt.push('\n');
+    }
+
+    write_file(&format!("{}.cdt", prefix), &cdt)?;
+    write_file(
+        &format!("{}.gtr", prefix),
+        &tree_file(row_hierarchy, "GENE"),
+    )?;
+    if let Some(cols) = col_hierarchy {
+        write_file(&format!("{}.atr", prefix), &tree_file(cols, "ARRY"))?;
+    }
+
+    return Ok(());
+}
+
+/// Builds the `.gtr`/`.atr` content: one `NODE<i>X  child1  child2  score` line per merge
+fn tree_file(hierarchy: &ClusterHierarchy, leaf_prefix: &str) -> String {
+    let n = hierarchy.leaf_size();
+    let node_name = |id: f64| -> String {
+        let id = id as usize;
+        if id < n {
+            return format!("{}{}X", leaf_prefix, id);
+        }
+        return format!("NODE{}X", id - n + 1);
+    };
+
+    let mut tree = String::new();
+    for (i, row) in hierarchy.linkage_matrix().iter().enumerate() {
+        tree.push_str(&format!(
+            "NODE{}X\t{}\t{}\t{}\n",
+            i + 1,
+            node_name(row[0]),
+            node_name(row[1]),
+            1.0 - row[2]
+        ));
+    }
+
+    return tree;
+}
+
+fn resolve_labels(
+    labels: Option<&[String]>,
+    n: usize,
+    axis: &str,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    match labels {
+        Some(labels) if labels.len() != n => {
+            return Err(format!("Expected {} {} labels but got {}", n, axis, labels.len()).into())
+        }
+        Some(labels) => {
+            return Ok(labels
+                .iter()
+                .map(|l| l.replace(['\t', '\n'], " "))
+                .collect())
+        }
+        None => return Ok((0..n).map(|i| i.to_string()).collect()),
+    }
+}
+
+fn write_file(fname: &str, content: &str) -> Result<(), Box<dyn Error>> {
+    let mut file = File::create(fname)?;
+    file.write_all(content.as_bytes())?;
+
+    return Ok(());
+}
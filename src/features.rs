@@ -0,0 +1,289 @@
+use polars::prelude::*;
+use rayon::prelude::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Matches data frame columns when building a `ColumnSpec`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ColumnSelector {
+    /// exact column name
+    Name(String),
+    /// regular expression on the column name, e.g. `^Metadata_`
+    Regex(String),
+    /// every integer or float column
+    Numeric,
+}
+
+/// Selects the feature columns of a data frame by name, regex or dtype
+///
+/// A column is used when it matches any `include` selector (every numeric column when
+/// `include` is empty) and no `exclude` selector. Integer and f32 columns are cast to f64.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ColumnSpec {
+    pub include: Vec<ColumnSelector>,
+    pub exclude: Vec<ColumnSelector>,
+}
+
+impl ColumnSpec {
+    /// Every numeric column
+    pub fn all_numeric() -> Self {
+        ColumnSpec::default()
+    }
+
+    /// Only the named columns
+    pub fn names(names: &[&str]) -> Self {
+        ColumnSpec {
+            include: names
+                .iter()
+                .map(|name| ColumnSelector::Name(name.to_string()))
+                .collect(),
+            exclude: vec![],
+        }
+    }
+
+    /// Every numeric column whose name does not match `pattern`
+    pub fn exclude_regex(pattern: &str) -> Self {
+        ColumnSpec {
+            include: vec![],
+            exclude: vec![ColumnSelector::Regex(pattern.to_string())],
+        }
+    }
+
+    /// Returns the names of the selected feature columns in data frame order
+    ///
+    /// Errors when a regex is invalid, a named column does not exist or an explicitly
+    /// selected column is not numeric.
+    pub fn resolve(&self, schema: &Schema) -> PolarsResult<Vec<String>> {
+        let include = compile(&self.include)?;
+        let exclude = compile(&self.exclude)?;
+
+        for selector in self.include.iter().chain(self.exclude.iter()) {
+            if let ColumnSelector::Name(name) = selector {
+                schema.try_get(name)?;
+            }
+        }
+
+        let mut selected: Vec<String> = Vec::new();
+        for (name, dtype) in schema.iter() {
+            let numeric = dtype.is_primitive_numeric();
+
+            let included = match include.is_empty() {
+                true => numeric,
+                false => include.iter().any(|sel| sel.matches(name, numeric)),
+            };
+            if !included || exclude.iter().any(|sel| sel.matches(name, numeric)) {
+                continue;
+            }
+
+            if !numeric {
+                polars_bail!(
+                    InvalidOperation: "column '{}' has non-numeric dtype {}", name, dtype
+                );
+            }
+            selected.push(name.to_string());
+        }
+
+        if selected.is_empty() {
+            polars_bail!(ComputeError: "no feature columns selected");
+        }
+
+        return Ok(selected);
+    }
+}
+
+/// Feature matrix extracted from a data frame
+#[derive(Debug, Clone)]
+pub struct FeatureMatrix {
+    /// rows x features
+    pub data: Vec<Vec<f64>>,
+    /// names of the feature columns
+    pub feature_names: Vec<String>,
+    /// row labels when a label column was given
+    pub labels: Option<Vec<String>>,
+}
+
+/// # Extracts the feature matrix selected by `spec` from a data frame
+///
+/// The label column (if any) is read as strings and never used as a feature.
+///
+/// ### params:
+/// - df: `DataFrame`
+/// - spec: `ColumnSpec` selecting the feature columns
+/// - label_col: optional name of the column holding row labels
+pub fn df_to_matrix(
+    df: &DataFrame,
+    spec: &ColumnSpec,
+    label_col: Option<&str>,
+) -> PolarsResult<FeatureMatrix> {
+    let mut spec = spec.clone();
+    if let Some(col) = label_col {
+        spec.exclude.push(ColumnSelector::Name(col.to_string()));
+    }
+    let feature_names = spec.resolve(df.schema())?;
+
+    let columns: Vec<&Column> = feature_names
+        .iter()
+        .map(|name| df.column(name))
+        .collect::<PolarsResult<_>>()?;
+    let data = columns_to_rows(&columns, df.height())?;
+
+    let labels = match label_col {
+        Some(col) => Some(df_labels(df, col)?),
+        None => None,
+    };
+
+    return Ok(FeatureMatrix {
+        data,
+        feature_names,
+        labels,
+    });
+}
+
+/// # Same as `df_to_matrix` but for a polars `LazyFrame`
+///
+/// The feature columns are resolved from the lazy schema and only those (plus the label
+/// column) are projected before collecting, so any filters on `lf` are applied before the
+/// data is materialised.
+pub fn lazy_to_matrix(
+    mut lf: LazyFrame,
+    spec: &ColumnSpec,
+    label_col: Option<&str>,
+) -> PolarsResult<FeatureMatrix> {
+    let mut projected_spec = spec.clone();
+    if let Some(col_name) = label_col {
+        projected_spec
+            .exclude
+            .push(ColumnSelector::Name(col_name.to_string()));
+    }
+
+    let schema = lf.collect_schema()?;
+    let mut names = projected_spec.resolve(&schema)?;
+    if let Some(col_name) = label_col {
+        schema.try_get(col_name)?;
+        names.push(col_name.to_string());
+    }
+
+    let exprs: Vec<Expr> = names.iter().map(|name| col(name.as_str())).collect();
+    let df = lf.select(exprs).collect()?;
+
+    return df_to_matrix(&df, spec, label_col);
+}
+
+/// Converts the data frame (minus `ignore_cols`) into a row major matrix
+pub(crate) fn df_to_vec(
+    df: &DataFrame,
+    ignore_cols: &Option<Vec<usize>>,
+) -> PolarsResult<Vec<Vec<f64>>> {
+    let columns: Vec<&Column> = df
+        .get_columns()
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !ignore_cols.as_ref().is_some_and(|cols| cols.contains(i)))
+        .map(|(_, column)| column)
+        .collect();
+
+    return columns_to_rows(&columns, df.height());
+}
+
+/// Reads every column once into a contiguous f64 buffer and transposes them into rows
+///
+/// Columns are read and rows assembled in parallel on the rayon thread pool.
+fn columns_to_rows(columns: &[&Column], nrows: usize) -> PolarsResult<Vec<Vec<f64>>> {
+    let buffers: Vec<Vec<f64>> = columns
+        .par_iter()
+        .map(|column| column_buffer(column))
+        .collect::<PolarsResult<_>>()?;
+
+    let matrix = (0..nrows)
+        .into_par_iter()
+        .map(|row_idx| buffers.iter().map(|buffer| buffer[row_idx]).collect())
+        .collect();
+
+    return Ok(matrix);
+}
+
+/// Casts a numeric column to f64 and copies its chunks into one buffer
+fn column_buffer(column: &Column) -> PolarsResult<Vec<f64>> {
+    if !column.dtype().is_primitive_numeric() {
+        polars_bail!(
+            InvalidOperation: "column '{}' has non-numeric dtype {}", column.name(), column.dtype()
+        );
+    }
+    if column.null_count() > 0 {
+        polars_bail!(ComputeError: "column '{}' contains null values", column.name());
+    }
+
+    let floats = column.cast(&DataType::Float64)?;
+    let mut buffer: Vec<f64> = Vec::with_capacity(floats.len());
+    for chunk in floats.f64()?.downcast_iter() {
+        buffer.extend_from_slice(chunk.values());
+    }
+
+    return Ok(buffer);
+}
+
+/// Reads a column as row labels, nulls become empty strings
+pub(crate) fn df_labels(df: &DataFrame, label_col: &str) -> PolarsResult<Vec<String>> {
+    let labels = df.column(label_col)?.cast(&DataType::String)?;
+
+    return Ok(labels
+        .str()?
+        .into_iter()
+        .map(|label| label.unwrap_or("").to_string())
+        .collect());
+}
+
+/// Adds the label column to the ignored columns
+pub(crate) fn with_label_col(
+    df: &DataFrame,
+    ignore_cols: &Option<Vec<usize>>,
+    label_col: &str,
+) -> PolarsResult<Option<Vec<usize>>> {
+    let label_idx = df.try_get_column_index(label_col)?;
+
+    let mut ignore_cols = ignore_cols.clone().unwrap_or_default();
+    ignore_cols.push(label_idx);
+
+    return Ok(Some(ignore_cols));
+}
+
+/// Names of the columns that `df_to_vec` keeps
+pub(crate) fn df_feature_names(df: &DataFrame, ignore_cols: &Option<Vec<usize>>) -> Vec<String> {
+    return df
+        .get_column_names()
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !ignore_cols.as_ref().is_some_and(|cols| cols.contains(i)))
+        .map(|(_, name)| name.to_string())
+        .collect();
+}
+
+/// A `ColumnSelector` with its regex compiled
+enum Compiled<'a> {
+    Name(&'a str),
+    Regex(Regex),
+    Numeric,
+}
+
+impl Compiled<'_> {
+    fn matches(&self, name: &str, numeric: bool) -> bool {
+        match self {
+            Compiled::Name(n) => *n == name,
+            Compiled::Regex(re) => re.is_match(name),
+            Compiled::Numeric => numeric,
+        }
+    }
+}
+
+fn compile(selectors: &[ColumnSelector]) -> PolarsResult<Vec<Compiled<'_>>> {
+    return selectors
+        .iter()
+        .map(|sel| match sel {
+            ColumnSelector::Name(name) => Ok(Compiled::Name(name)),
+            ColumnSelector::Regex(pattern) => Regex::new(pattern).map(Compiled::Regex).map_err(
+                |e| polars_err!(ComputeError: "invalid column regex '{}': {}", pattern, e),
+            ),
+            ColumnSelector::Numeric => Ok(Compiled::Numeric),
+        })
+        .collect();
+}
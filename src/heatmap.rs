@@ -0,0 +1,183 @@
+use std::{error::Error, fs::File, io::Write};
+
+use polars::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    create_hierarchy,
+    features::{df_feature_names, df_labels, df_to_vec, with_label_col},
+    heatmap_svg, transpose, write_html_report, ClusterHierarchy, HeatmapStyle, LinkageMethod,
+    Metric, ReportOptions,
+};
+
+/// Output for two-way (row and column) clustering
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BiclusteredHeatmap {
+    row_hierarchy: ClusterHierarchy,
+    col_hierarchy: ClusterHierarchy,
+    reordered: Vec<Vec<f64>>,
+}
+
+impl BiclusteredHeatmap {
+    /// Hierarchy of the rows (samples)
+    pub fn row_hierarchy(&self) -> &ClusterHierarchy {
+        &self.row_hierarchy
+    }
+
+    /// Hierarchy of the columns (features)
+    pub fn col_hierarchy(&self) -> &ClusterHierarchy {
+        &self.col_hierarchy
+    }
+
+    /// Leaf ordering of the rows
+    pub fn row_order(&self) -> Vec<usize> {
+        self.row_hierarchy.leaf_ordering()
+    }
+
+    /// Leaf ordering of the columns
+    pub fn col_order(&self) -> Vec<usize> {
+        self.col_hierarchy.leaf_ordering()
+    }
+
+    /// The input matrix with rows and columns in leaf order
+    pub fn reordered_matrix(&self) -> &[Vec<f64>] {
+        &self.reordered
+    }
+
+    /// Attaches row and/or column labels to the two hierarchies
+    pub fn set_labels(
+        &mut self,
+        row_labels: Option<Vec<String>>,
+        col_labels: Option<Vec<String>>,
+    ) -> Result<(), Box<dyn Error>> {
+        if let Some(labels) = row_labels {
+            self.row_hierarchy.set_labels(labels)?;
+        }
+        if let Some(labels) = col_labels {
+            self.col_hierarchy.set_labels(labels)?;
+        }
+
+        return Ok(());
+    }
+
+    /// Renders the clustered heatmap with both dendrograms in svg, see `heatmap_svg`
+    pub fn to_svg(&self, style: &HeatmapStyle) -> Result<String, Box<dyn Error>> {
+        return heatmap_svg(
+            &self.original_matrix(),
+            &self.row_hierarchy,
+            Some(&self.col_hierarchy),
+            style,
+        );
+    }
+
+    /// Writes the clustered heatmap as svg
+    pub fn write_svg(&self, fname: &str, style: &HeatmapStyle) -> Result<(), Box<dyn Error>> {
+        let mut file = File::create(fname)?;
+        file.write_all(self.to_svg(style)?.as_bytes())?;
+
+        return Ok(());
+    }
+
+    /// Writes the clustered heatmap as png
+    #[cfg(feature = "png")]
+    pub fn write_png(&self, fname: &str, style: &HeatmapStyle) -> Result<(), Box<dyn Error>> {
+        return crate::write_heatmap_png(
+            fname,
+            &self.original_matrix(),
+            &self.row_hierarchy,
+            Some(&self.col_hierarchy),
+            style,
+        );
+    }
+
+    /// Writes the interactive html report with both dendrograms, see `html_report`
+    pub fn write_html(&self, fname: &str, options: &ReportOptions) -> Result<(), Box<dyn Error>> {
+        return write_html_report(
+            fname,
+            &self.original_matrix(),
+            &self.row_hierarchy,
+            Some(&self.col_hierarchy),
+            options,
+        );
+    }
+
+    /// The input matrix in its original row and column order
+    fn original_matrix(&self) -> Vec<Vec<f64>> {
+        let row_order = self.row_order();
+        let col_order = self.col_order();
+
+        let mut data = vec![vec![0.0; col_order.len()]; row_order.len()];
+        for (i, &r) in row_order.iter().enumerate() {
+            for (j, &c) in col_order.iter().enumerate() {
+                data[r][c] = self.reordered[i][j];
+            }
+        }
+
+        return data;
+    }
+}
+
+/// # Clusters both the rows and the columns of a data matrix
+///
+/// Rows and columns are clustered independently, each with their own metric and linkage.
+///
+/// ### params:
+/// - raw_data: `&[Vec<f64>]` (outer slice are the rows, inner Vec are the features)
+/// - row_metric / row_link: `Metric` and `LinkageMethod` used for the rows
+/// - col_metric / col_link: `Metric` and `LinkageMethod` used for the columns
+pub fn create_biclustered_heatmap(
+    raw_data: &[Vec<f64>],
+    row_metric: Metric,
+    row_link: LinkageMethod,
+    col_metric: Metric,
+    col_link: LinkageMethod,
+) -> Result<BiclusteredHeatmap, Box<dyn Error>> {
+    let ncols = raw_data.first().map(|row| row.len()).unwrap_or(0);
+    if raw_data.iter().any(|row| row.len() != ncols) {
+        return Err("All rows of the data matrix must have the same length".into());
+    }
+
+    let row_hierarchy = create_hierarchy(raw_data, row_metric, row_link)?;
+    let col_hierarchy = create_hierarchy(&transpose(raw_data), col_metric, col_link)?;
+
+    let col_order = col_hierarchy.leaf_ordering();
+    let reordered = row_hierarchy
+        .leaf_ordering()
+        .iter()
+        .map(|&r| col_order.iter().map(|&c| raw_data[r][c]).collect())
+        .collect();
+
+    return Ok(BiclusteredHeatmap {
+        row_hierarchy,
+        col_hierarchy,
+        reordered,
+    });
+}
+
+/// Same as `create_biclustered_heatmap` except converts data frame into the raw data matrix
+///
+/// Columns are labelled with the data frame column names and rows with `label_col` if given.
+pub fn create_biclustered_heatmap_from_df(
+    df: &DataFrame,
+    row_metric: Metric,
+    row_link: LinkageMethod,
+    col_metric: Metric,
+    col_link: LinkageMethod,
+    ignore_cols: &Option<Vec<usize>>,
+    label_col: Option<&str>,
+) -> Result<BiclusteredHeatmap, Box<dyn Error>> {
+    let (row_labels, ignore_cols) = match label_col {
+        Some(col) => (
+            Some(df_labels(df, col)?),
+            with_label_col(df, ignore_cols, col)?,
+        ),
+        None => (None, ignore_cols.clone()),
+    };
+
+    let data = df_to_vec(df, &ignore_cols)?;
+    let mut heatmap =
+        create_biclustered_heatmap(&data, row_metric, row_link, col_metric, col_link)?;
+    heatmap.set_labels(row_labels, Some(df_feature_names(df, &ignore_cols)))?;
+
+    return Ok(heatmap);
+}
@@ -1,12 +1,38 @@
+#![allow(clippy::needless_return)]
+
 mod clustering;
+mod export;
+mod features;
+mod heatmap;
 mod matrix_op;
+mod plot;
+mod readers;
 
+use features::{df_labels, df_to_vec, with_label_col};
+use ndarray::ArrayView2;
 use polars::prelude::*;
-use std::error::Error;
+use std::{error::Error, path::Path};
 
 pub use clustering::DendrogramNode;
-pub use clustering::{hierarchical_clustering, ClusterHierarchy, LinkageMethod};
-pub use matrix_op::{calculate_matrix, Metric};
+pub use clustering::{
+    bakers_gamma, cophenetic_correlation, fowlkes_mallows, fowlkes_mallows_curve,
+    hierarchical_clustering, hierarchical_clustering_from_array, robinson_foulds, untangle,
+    ClusterHierarchy, D3Options, DotOptions, LinkageMethod, Tanglegram, TreeView, UntangleMethod,
+    WeightAggregation,
+};
+pub use export::{html_report, write_html_report, write_treeview, ReportOptions};
+pub use features::{df_to_matrix, lazy_to_matrix, ColumnSelector, ColumnSpec, FeatureMatrix};
+pub use heatmap::{
+    create_biclustered_heatmap, create_biclustered_heatmap_from_df, BiclusteredHeatmap,
+};
+pub use matrix_op::{calculate_matrix, calculate_matrix_from_array, transpose, Metric};
+#[cfg(feature = "png")]
+pub use plot::write_heatmap_png;
+pub use plot::{
+    heatmap_svg, write_heatmap_svg, Annotation, ColorMap, DendrogramStyle, HeatmapStyle,
+    Orientation, TanglegramStyle,
+};
+pub use readers::{scan_csv_files, scan_parquet_files};
 
 /// # Given a data matrix, generate hierarchical clustering of data.
 ///
@@ -27,7 +53,23 @@ pub fn create_hierarchy(
     return hierarchy;
 }
 
-/// Same as `create_hierarchy` except converts data frame into the raw data matrix
+/// Same as `create_hierarchy` except takes the raw data as an ndarray view (rows x features)
+pub fn create_hierarchy_from_array(
+    raw_data: ArrayView2<f64>,
+    mat_metric: Metric,
+    link_method: LinkageMethod,
+) -> Result<ClusterHierarchy, Box<dyn Error>> {
+    let dist_matrix = calculate_matrix_from_array(raw_data, mat_metric, true);
+    return hierarchical_clustering_from_array(dist_matrix.view(), link_method);
+}
+
+/// # Same as `create_hierarchy` except converts data frame into the raw data matrix
+///
+/// Every column not in `ignore_cols` is a feature. Integer and f32 columns are cast to f64;
+/// any other dtype, or a null value anywhere in a feature column, fails the call.
+///
+/// ### params:
+/// - ignore_cols: positions of the columns to leave out (e.g. metadata columns)
 pub fn create_hierarchy_from_df(
     df: &DataFrame,
     mat_metric: Metric,
@@ -38,34 +80,107 @@ pub fn create_hierarchy_from_df(
     return create_hierarchy(&data, mat_metric, link_method);
 }
 
-fn df_to_vec(df: &DataFrame, ignore_cols: &Option<Vec<usize>>) -> PolarsResult<Vec<Vec<f64>>> {
-    let ignore_cols = match ignore_cols {
-        Some(cols) => cols,
-        None => &vec![],
-    };
+/// # Same as `create_hierarchy` but attaches leaf labels to the hierarchy
+///
+/// ### params:
+/// - labels: one label per row of `raw_data`
+pub fn create_labeled_hierarchy(
+    raw_data: &[Vec<f64>],
+    mat_metric: Metric,
+    link_method: LinkageMethod,
+    labels: Vec<String>,
+) -> Result<ClusterHierarchy, Box<dyn Error>> {
+    let mut hierarchy = create_hierarchy(raw_data, mat_metric, link_method)?;
+    hierarchy.set_labels(labels)?;
+
+    return Ok(hierarchy);
+}
+
+/// # Same as `create_hierarchy_from_df` but labels the leaves with a data frame column
+///
+/// The label column is cast to strings and is never used as a feature.
+///
+/// ### params:
+/// - label_col: name of the column holding the row labels
+pub fn create_labeled_hierarchy_from_df(
+    df: &DataFrame,
+    mat_metric: Metric,
+    link_method: LinkageMethod,
+    ignore_cols: &Option<Vec<usize>>,
+    label_col: &str,
+) -> Result<ClusterHierarchy, Box<dyn Error>> {
+    let labels = df_labels(df, label_col)?;
+    let ignore_cols = with_label_col(df, ignore_cols, label_col)?;
 
-    let nrows = df.height();
-    let ncols = df.width();
+    let data = df_to_vec(df, &ignore_cols)?;
+    return create_labeled_hierarchy(&data, mat_metric, link_method, labels);
+}
 
-    let mut matrix: Vec<Vec<f64>> = Vec::with_capacity(nrows);
+/// # Same as `create_hierarchy_from_df` but selects the feature columns with a `ColumnSpec`
+///
+/// Columns are picked by name, regex or dtype instead of position, integer and f32 columns are
+/// cast to f64 and the optional label column labels the leaves.
+///
+/// ### params:
+/// - spec: `ColumnSpec` selecting the feature columns
+/// - label_col: optional name of the column holding the row labels
+pub fn create_hierarchy_from_df_with_spec(
+    df: &DataFrame,
+    mat_metric: Metric,
+    link_method: LinkageMethod,
+    spec: &ColumnSpec,
+    label_col: Option<&str>,
+) -> Result<ClusterHierarchy, Box<dyn Error>> {
+    let features = df_to_matrix(df, spec, label_col)?;
 
-    let cols: Vec<usize> = (0..ncols).filter(|x| !ignore_cols.contains(x)).collect();
+    let mut hierarchy = create_hierarchy(&features.data, mat_metric, link_method)?;
+    if let Some(labels) = features.labels {
+        hierarchy.set_labels(labels)?;
+    }
 
-    for row_idx in 0..nrows {
-        let mut row: Vec<f64> = Vec::with_capacity(cols.len());
-        for col_idx in cols.iter() {
-            // get col as f64
-            let series = df.select_at_idx(col_idx.clone()).unwrap();
-            let float_col = series.f64()?.clone();
+    return Ok(hierarchy);
+}
 
-            let val = float_col.get(row_idx).ok_or_else(|| {
-                PolarsError::ComputeError(format!("Index out of bounds for row {}", row_idx).into())
-            })?;
+/// # Same as `create_hierarchy_from_df_with_spec` but for a polars `LazyFrame`
+///
+/// Only the selected feature columns (and the label column) are projected before the frame is
+/// collected, so filters and column pruning are pushed down to the scan.
+pub fn create_hierarchy_from_lazy(
+    lf: LazyFrame,
+    mat_metric: Metric,
+    link_method: LinkageMethod,
+    spec: &ColumnSpec,
+    label_col: Option<&str>,
+) -> Result<ClusterHierarchy, Box<dyn Error>> {
+    let features = lazy_to_matrix(lf, spec, label_col)?;
 
-            row.push(val);
-        }
-        matrix.push(row);
+    let mut hierarchy = create_hierarchy(&features.data, mat_metric, link_method)?;
+    if let Some(labels) = features.labels {
+        hierarchy.set_labels(labels)?;
     }
 
-    return Ok(matrix);
+    return Ok(hierarchy);
+}
+
+/// # Clusters the rows of one or many Parquet files
+///
+/// The files are concatenated (they must share a schema), the feature columns are picked with
+/// `spec` and the optional `label_col` (e.g. a `Metadata_` column) labels the leaves. Only the
+/// needed columns are read from disk.
+///
+/// ### params:
+/// - paths: Parquet files, concatenated in order
+/// - mat_metric: `Metric` (Enum)
+/// - link_method: `LinkageMethod` (Enum)
+/// - spec: `ColumnSpec` selecting the feature columns
+/// - label_col: optional name of the column holding the row labels
+pub fn create_hierarchy_from_parquet<P: AsRef<Path>>(
+    paths: &[P],
+    mat_metric: Metric,
+    link_method: LinkageMethod,
+    spec: &ColumnSpec,
+    label_col: Option<&str>,
+) -> Result<ClusterHierarchy, Box<dyn Error>> {
+    let lf = scan_parquet_files(paths)?;
+    return create_hierarchy_from_lazy(lf, mat_metric, link_method, spec, label_col);
 }
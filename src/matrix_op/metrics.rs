@@ -1,6 +1,6 @@
 use super::operations::*;
 
-pub(in crate::matrix_op) fn centered_correlation(u: &Vec<f64>, v: &Vec<f64>) -> f64 {
+pub(in crate::matrix_op) fn centered_correlation(u: &[f64], v: &[f64]) -> f64 {
     let umu = mean(u).unwrap();
     let vmu = mean(v).unwrap();
 
@@ -15,15 +15,15 @@ pub(in crate::matrix_op) fn centered_correlation(u: &Vec<f64>, v: &Vec<f64>) ->
     return res;
 }
 
-pub(in crate::matrix_op) fn pearson_r(x: &Vec<f64>, y: &Vec<f64>, distance: bool) -> f64 {
+pub(crate) fn pearson_r(x: &[f64], y: &[f64], distance: bool) -> f64 {
     let n = x.len() as f64;
 
     let sum_x = sum(x).unwrap();
     let sum_y = sum(y).unwrap();
-    let sum_xy = multiply(&x, &y).iter().sum::<f64>();
+    let sum_xy = multiply(x, y).iter().sum::<f64>();
 
-    let sum_x_sq = x.iter().map(|xi| xi * xi).into_iter().sum::<f64>();
-    let sum_y_sq = y.iter().map(|xi| xi * xi).into_iter().sum::<f64>();
+    let sum_x_sq = x.iter().map(|xi| xi * xi).sum::<f64>();
+    let sum_y_sq = y.iter().map(|xi| xi * xi).sum::<f64>();
 
     let numerator = n * sum_xy - sum_x * sum_y;
     let denominator = ((n * sum_x_sq - sum_x.powi(2)) * (n * sum_y_sq - sum_y.powi(2))).sqrt();
@@ -1,9 +1,10 @@
-use std::usize;
+use std::{borrow::Cow, str::FromStr};
 
+use ndarray::{Array2, ArrayView2};
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use serde::{Deserialize, Serialize};
 
-mod metrics;
+pub(crate) mod metrics;
 mod operations;
 
 #[derive(Clone, Copy, Serialize, Deserialize)]
@@ -12,29 +13,89 @@ pub enum Metric {
     Distance,
 }
 
+impl FromStr for Metric {
+    type Err = String;
+
+    /// Parses `pearson` or `distance` (case insensitive)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "pearson" => return Ok(Metric::Pearson),
+            "distance" => return Ok(Metric::Distance),
+            _ => {
+                return Err(format!(
+                    "Unknown metric '{}', expected pearson or distance",
+                    s
+                ))
+            }
+        }
+    }
+}
+
 pub fn calculate_matrix(mat: &[Vec<f64>], metric: Metric, dist: bool) -> Vec<Vec<f64>> {
     let n = mat.len();
 
     let mut distances = vec![vec![0.0; n]; n];
 
-    let pairwise_scores: Vec<(usize, usize, f64)> = (0..n)
+    let rows: Vec<&[f64]> = mat.iter().map(|row| row.as_slice()).collect();
+    for (i, j, score) in pairwise_scores(&rows, metric, dist) {
+        distances[i][j] = score;
+        distances[j][i] = score;
+    }
+
+    return distances;
+}
+
+/// Same as `calculate_matrix` but takes an ndarray view (rows are the items) and returns an `Array2`
+pub fn calculate_matrix_from_array(
+    mat: ArrayView2<f64>,
+    metric: Metric,
+    dist: bool,
+) -> Array2<f64> {
+    let n = mat.nrows();
+
+    let mut distances = Array2::<f64>::zeros((n, n));
+
+    // borrow contiguous rows, copy the others (e.g. column major or strided views)
+    let owned_rows: Vec<Cow<[f64]>> = mat
+        .rows()
+        .into_iter()
+        .map(|row| match row.to_slice() {
+            Some(slice) => Cow::Borrowed(slice),
+            None => Cow::Owned(row.to_vec()),
+        })
+        .collect();
+    let rows: Vec<&[f64]> = owned_rows.iter().map(|row| row.as_ref()).collect();
+
+    for (i, j, score) in pairwise_scores(&rows, metric, dist) {
+        distances[[i, j]] = score;
+        distances[[j, i]] = score;
+    }
+
+    return distances;
+}
+
+/// Swaps rows and columns of a row major matrix, e.g. to cluster the features instead of the items
+pub fn transpose(mat: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    let ncols = mat.first().map(|row| row.len()).unwrap_or(0);
+    return (0..ncols)
+        .map(|c| mat.iter().map(|row| row[c]).collect())
+        .collect();
+}
+
+fn pairwise_scores(rows: &[&[f64]], metric: Metric, dist: bool) -> Vec<(usize, usize, f64)> {
+    let n = rows.len();
+
+    return (0..n)
         .into_par_iter()
         .flat_map_iter(|i| {
             (i..n).map(move |j| {
                 let score = match metric {
-                    Metric::Pearson => metrics::pearson_r(&mat[i], &mat[j], dist),
-                    Metric::Distance => metrics::centered_correlation(&mat[i], &mat[j]),
+                    Metric::Pearson => metrics::pearson_r(rows[i], rows[j], dist),
+                    Metric::Distance => metrics::centered_correlation(rows[i], rows[j]),
                 };
 
                 (i, j, score)
             })
         })
         .collect();
-
-    for (i, j, score) in pairwise_scores {
-        distances[i][j] = score;
-        distances[j][i] = score;
-    }
-
-    return distances;
 }
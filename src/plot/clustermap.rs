@@ -0,0 +1,597 @@
+use std::{error::Error, fs::File, io::Write};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    dendrogram::{branches_svg, Rect},
+    svg, DendrogramStyle, Orientation,
+};
+use crate::ClusterHierarchy;
+
+/// Diverging colormap for the heatmap cells
+///
+/// Values are clipped to `vmin..=vmax` and interpolated from `low` over `mid` (at `center`)
+/// to `high`. When `vmin`/`vmax` are not given the range is symmetric around `center` and
+/// covers every finite value.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColorMap {
+    pub low: [u8; 3],
+    pub mid: [u8; 3],
+    pub high: [u8; 3],
+    pub center: f64,
+    pub vmin: Option<f64>,
+    pub vmax: Option<f64>,
+    /// color of NaN cells
+    pub nan_color: [u8; 3],
+}
+
+impl Default for ColorMap {
+    fn default() -> Self {
+        ColorMap {
+            low: [59, 76, 192],
+            mid: [247, 247, 247],
+            high: [180, 4, 38],
+            center: 0.0,
+            vmin: None,
+            vmax: None,
+            nan_color: [200, 200, 200],
+        }
+    }
+}
+
+impl ColorMap {
+    /// Color range actually used for `data`
+    pub(crate) fn range(&self, data: &[Vec<f64>]) -> (f64, f64) {
+        let dev = data
+            .iter()
+            .flatten()
+            .filter(|v| v.is_finite())
+            .map(|v| (v - self.center).abs())
+            .fold(0.0, f64::max);
+        let dev = if dev > 0.0 { dev } else { 1.0 };
+
+        return (
+            self.vmin.unwrap_or(self.center - dev),
+            self.vmax.unwrap_or(self.center + dev),
+        );
+    }
+
+    fn color(&self, val: f64, vmin: f64, vmax: f64) -> [u8; 3] {
+        if val.is_nan() {
+            return self.nan_color;
+        }
+
+        let val = val.clamp(vmin, vmax);
+        let (from, to, t) = if val < self.center {
+            (
+                self.mid,
+                self.low,
+                fraction(self.center - val, self.center - vmin),
+            )
+        } else {
+            (
+                self.mid,
+                self.high,
+                fraction(val - self.center, vmax - self.center),
+            )
+        };
+
+        let mut rgb = [0u8; 3];
+        for k in 0..3 {
+            rgb[k] = (from[k] as f64 + t * (to[k] as f64 - from[k] as f64)).round() as u8;
+        }
+        return rgb;
+    }
+}
+
+/// Categorical metadata drawn as a color bar next to the heatmap
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Annotation {
+    pub name: String,
+    /// one category per row (or column), indexed like the data matrix
+    pub values: Vec<String>,
+}
+
+/// Options for `heatmap_svg`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeatmapStyle {
+    pub colormap: ColorMap,
+    pub cell_width: f64,
+    pub cell_height: f64,
+    /// size in pixels of the dendrograms on the margins, 0 hides them
+    pub dendrogram_size: f64,
+    /// branch colors and line width of the dendrograms (the orientation is ignored)
+    pub dendrogram: DendrogramStyle,
+    /// row names, defaulting to the row hierarchy labels and then to the row index
+    pub row_labels: Option<Vec<String>>,
+    /// column names, defaulting to the column hierarchy labels and then to the column index
+    pub col_labels: Option<Vec<String>>,
+    pub show_row_labels: bool,
+    pub show_col_labels: bool,
+    pub font_size: f64,
+    /// color bars drawn between the row dendrogram and the heatmap
+    pub row_annotations: Vec<Annotation>,
+    /// color bars drawn between the column dendrogram and the heatmap
+    pub col_annotations: Vec<Annotation>,
+    /// width in pixels of every annotation bar
+    pub annotation_size: f64,
+    /// colors cycled through for the annotation categories
+    pub annotation_palette: Vec<String>,
+    /// draw the color scale and the annotation legends on the right
+    pub show_legend: bool,
+}
+
+impl Default for HeatmapStyle {
+    fn default() -> Self {
+        HeatmapStyle {
+            colormap: ColorMap::default(),
+            cell_width: 12.0,
+            cell_height: 12.0,
+            dendrogram_size: 100.0,
+            dendrogram: DendrogramStyle {
+                color_threshold: Some(0.0),
+                above_threshold_color: "#000000".to_string(),
+                ..Default::default()
+            },
+            row_labels: None,
+            col_labels: None,
+            show_row_labels: true,
+            show_col_labels: true,
+            font_size: 10.0,
+            row_annotations: vec![],
+            col_annotations: vec![],
+            annotation_size: 10.0,
+            annotation_palette: [
+                "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2",
+                "#7f7f7f", "#bcbd22", "#17becf",
+            ]
+            .iter()
+            .map(|c| c.to_string())
+            .collect(),
+            show_legend: true,
+        }
+    }
+}
+
+/// # Renders a clustered heatmap in svg
+///
+/// Rows and columns are drawn in leaf order with the dendrograms attached on the left and top
+/// margins, annotation color bars between the dendrograms and the cells, and the color scale
+/// and annotation legends on the right.
+///
+/// ### params:
+/// - data: `&[Vec<f64>]` the clustered data matrix (rows x features), NaN cells use the
+///   `nan_color`
+/// - row_hierarchy: `ClusterHierarchy` of the rows
+/// - col_hierarchy: optional `ClusterHierarchy` of the columns, columns keep their order
+///   without it
+/// - style: `HeatmapStyle` with the colormap, sizes, labels and annotations
+pub fn heatmap_svg(
+    data: &[Vec<f64>],
+    row_hierarchy: &ClusterHierarchy,
+    col_hierarchy: Option<&ClusterHierarchy>,
+    style: &HeatmapStyle,
+) -> Result<String, Box<dyn Error>> {
+    let (nrows, ncols) = check_inputs(
+        data,
+        row_hierarchy,
+        col_hierarchy,
+        &style.row_annotations,
+        &style.col_annotations,
+    )?;
+
+    let row_names = resolve_labels(
+        style.row_labels.as_deref(),
+        Some(row_hierarchy),
+        nrows,
+        "row",
+    )?;
+    let col_names = resolve_labels(style.col_labels.as_deref(), col_hierarchy, ncols, "column")?;
+
+    let row_order = leaf_order(Some(row_hierarchy), nrows);
+    let col_order = leaf_order(col_hierarchy, ncols);
+    let (vmin, vmax) = style.colormap.range(data);
+
+    let fs = style.font_size;
+    let char_width = fs * 0.6;
+    let text_width = |labels: &[String]| -> f64 {
+        let max_chars = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+        return max_chars as f64 * char_width + 6.0;
+    };
+
+    // layout
+    let margin = 10.0;
+    let gap = 2.0;
+    let row_dend = style.dendrogram_size;
+    let col_dend = match col_hierarchy {
+        Some(_) => style.dendrogram_size,
+        None => 0.0,
+    };
+    let row_ann = style.row_annotations.len() as f64 * (style.annotation_size + gap);
+    let col_ann = style.col_annotations.len() as f64 * (style.annotation_size + gap);
+    let row_label_width = if style.show_row_labels {
+        text_width(&row_names)
+    } else {
+        0.0
+    };
+    let col_label_height = if style.show_col_labels {
+        text_width(&col_names)
+    } else {
+        0.0
+    };
+
+    let heat = Rect {
+        x0: margin + row_dend + row_ann,
+        y0: margin + col_dend + col_ann,
+        x1: margin + row_dend + row_ann + ncols as f64 * style.cell_width,
+        y1: margin + col_dend + col_ann + nrows as f64 * style.cell_height,
+    };
+
+    let legend_x = heat.x1 + row_label_width + 20.0;
+    let colorbar_height = (heat.y1 - heat.y0).clamp(60.0, 150.0);
+    let row_step = fs + 4.0;
+
+    let mut legend_width = 0.0f64;
+    let mut legend_height = 0.0;
+    let mut categories: Vec<Vec<(String, String)>> = Vec::new();
+    for ann in style
+        .row_annotations
+        .iter()
+        .chain(style.col_annotations.iter())
+    {
+        let cats = category_colors(ann, &style.annotation_palette);
+        let names: Vec<String> = cats.iter().map(|(cat, _)| cat.clone()).collect();
+        legend_width = legend_width
+            .max(text_width(&names) + 14.0)
+            .max(text_width(std::slice::from_ref(&ann.name)));
+        legend_height += row_step * (cats.len() + 1) as f64 + 10.0;
+        categories.push(cats);
+    }
+    if style.show_legend {
+        let ticks = svg::ticks(vmin, vmax, 4);
+        let tick_names: Vec<String> = ticks.iter().map(|&t| svg::tick_label(t, &ticks)).collect();
+        legend_width = legend_width.max(15.0 + 6.0 + text_width(&tick_names));
+        legend_height += colorbar_height + 20.0;
+    } else {
+        legend_width = 0.0;
+        legend_height = 0.0;
+    }
+
+    let width = match style.show_legend {
+        true => legend_x + legend_width + margin,
+        false => heat.x1 + row_label_width + margin,
+    };
+    let height = (heat.y1 + col_label_height + margin).max(heat.y0 + legend_height + margin);
+
+    let mut out = svg::open(width, height);
+
+    // cells
+    out.push_str("<g shape-rendering=\"crispEdges\">\n");
+    for (i, &r) in row_order.iter().enumerate() {
+        for (j, &c) in col_order.iter().enumerate() {
+            out.push_str(&format!(
+                "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"{}\"/>\n",
+                heat.x0 + j as f64 * style.cell_width,
+                heat.y0 + i as f64 * style.cell_height,
+                style.cell_width,
+                style.cell_height,
+                hex(style.colormap.color(data[r][c], vmin, vmax))
+            ));
+        }
+    }
+
+    // annotation bars
+    for (k, ann) in style.row_annotations.iter().enumerate() {
+        let colors = category_colors(ann, &style.annotation_palette);
+        let x = margin + row_dend + k as f64 * (style.annotation_size + gap);
+        for (i, &r) in row_order.iter().enumerate() {
+            out.push_str(&format!(
+                "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"{}\"/>\n",
+                x,
+                heat.y0 + i as f64 * style.cell_height,
+                style.annotation_size,
+                style.cell_height,
+                svg::escape(lookup(&colors, &ann.values[r]))
+            ));
+        }
+    }
+    for (k, ann) in style.col_annotations.iter().enumerate() {
+        let colors = category_colors(ann, &style.annotation_palette);
+        let y = margin + col_dend + k as f64 * (style.annotation_size + gap);
+        for (j, &c) in col_order.iter().enumerate() {
+            out.push_str(&format!(
+                "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"{}\"/>\n",
+                heat.x0 + j as f64 * style.cell_width,
+                y,
+                style.cell_width,
+                style.annotation_size,
+                svg::escape(lookup(&colors, &ann.values[c]))
+            ));
+        }
+    }
+    out.push_str("</g>\n");
+
+    // dendrograms, the leaf axis spans the cells so leaves sit on the cell centers
+    if row_dend > 0.0 && nrows > 1 {
+        let dend_style = DendrogramStyle {
+            orientation: Orientation::Left,
+            ..style.dendrogram.clone()
+        };
+        let rect = Rect {
+            x0: margin,
+            y0: heat.y0,
+            x1: margin + row_dend - gap,
+            y1: heat.y1,
+        };
+        out.push_str(&branches_svg(row_hierarchy, rect, &dend_style));
+    }
+    if let Some(cols) = col_hierarchy {
+        if col_dend > 0.0 && ncols > 1 {
+            let dend_style = DendrogramStyle {
+                orientation: Orientation::Top,
+                ..style.dendrogram.clone()
+            };
+            let rect = Rect {
+                x0: heat.x0,
+                y0: margin,
+                x1: heat.x1,
+                y1: margin + col_dend - gap,
+            };
+            out.push_str(&branches_svg(cols, rect, &dend_style));
+        }
+    }
+
+    // labels
+    out.push_str(&format!("<g font-size=\"{}\" fill=\"black\">\n", fs));
+    if style.show_row_labels {
+        for (i, &r) in row_order.iter().enumerate() {
+            out.push_str(&format!(
+                "<text x=\"{:.2}\" y=\"{:.2}\" dominant-baseline=\"middle\">{}</text>\n",
+                heat.x1 + 4.0,
+                heat.y0 + (i as f64 + 0.5) * style.cell_height,
+                svg::escape(&row_names[r])
+            ));
+        }
+    }
+    if style.show_col_labels {
+        for (j, &c) in col_order.iter().enumerate() {
+            out.push_str(&format!(
+                "<text x=\"{x:.2}\" y=\"{y:.2}\" transform=\"rotate(90 {x:.2} {y:.2})\" dominant-baseline=\"middle\">{}</text>\n",
+                svg::escape(&col_names[c]),
+                x = heat.x0 + (j as f64 + 0.5) * style.cell_width,
+                y = heat.y1 + 4.0
+            ));
+        }
+    }
+    out.push_str("</g>\n");
+
+    if style.show_legend {
+        out.push_str(&legend_svg(
+            style,
+            &categories,
+            (vmin, vmax),
+            legend_x,
+            heat.y0,
+            colorbar_height,
+        ));
+    }
+
+    out.push_str("</svg>\n");
+    return Ok(out);
+}
+
+/// Writes the clustered heatmap as svg, see `heatmap_svg`
+pub fn write_heatmap_svg(
+    fname: &str,
+    data: &[Vec<f64>],
+    row_hierarchy: &ClusterHierarchy,
+    col_hierarchy: Option<&ClusterHierarchy>,
+    style: &HeatmapStyle,
+) -> Result<(), Box<dyn Error>> {
+    let svg = heatmap_svg(data, row_hierarchy, col_hierarchy, style)?;
+
+    let mut file = File::create(fname)?;
+    file.write_all(svg.as_bytes())?;
+
+    return Ok(());
+}
+
+/// Writes the clustered heatmap as png (one svg pixel per png pixel), see `heatmap_svg`
+#[cfg(feature = "png")]
+pub fn write_heatmap_png(
+    fname: &str,
+    data: &[Vec<f64>],
+    row_hierarchy: &ClusterHierarchy,
+    col_hierarchy: Option<&ClusterHierarchy>,
+    style: &HeatmapStyle,
+) -> Result<(), Box<dyn Error>> {
+    let svg = heatmap_svg(data, row_hierarchy, col_hierarchy, style)?;
+    return svg::write_png(&svg, fname);
+}
+
+/// Checks the matrix shape against the hierarchies and annotations, returns `(rows, cols)`
+pub(crate) fn check_inputs(
+    data: &[Vec<f64>],
+    row_hierarchy: &ClusterHierarchy,
+    col_hierarchy: Option<&ClusterHierarchy>,
+    row_annotations: &[Annotation],
+    col_annotations: &[Annotation],
+) -> Result<(usize, usize), Box<dyn Error>> {
+    let nrows = data.len();
+    let ncols = data.first().map(|row| row.len()).unwrap_or(0);
+
+    if data.iter().any(|row| row.len() != ncols) {
+        return Err("All rows of the data matrix must have the same length".into());
+    }
+    if row_hierarchy.leaf_size() != nrows {
+        return Err(format!(
+            "Row hierarchy has {} leaves but the data has {} rows",
+            row_hierarchy.leaf_size(),
+            nrows
+        )
+        .into());
+    }
+    if let Some(cols) = col_hierarchy {
+        if cols.leaf_size() != ncols {
+            return Err(format!(
+                "Column hierarchy has {} leaves but the data has {} columns",
+                cols.leaf_size(),
+                ncols
+            )
+            .into());
+        }
+    }
+    for (annotations, n, axis) in [
+        (row_annotations, nrows, "row"),
+        (col_annotations, ncols, "column"),
+    ] {
+        for ann in annotations {
+            if ann.values.len() != n {
+                return Err(format!(
+                    "{} annotation '{}' has {} values but expected {}",
+                    axis,
+                    ann.name,
+                    ann.values.len(),
+                    n
+                )
+                .into());
+            }
+        }
+    }
+
+    return Ok((nrows, ncols));
+}
+
+/// Color scale followed by one legend block per annotation
+fn legend_svg(
+    style: &HeatmapStyle,
+    categories: &[Vec<(String, String)>],
+    (vmin, vmax): (f64, f64),
+    x: f64,
+    y: f64,
+    colorbar_height: f64,
+) -> String {
+    let cmap = &style.colormap;
+    let fs = style.font_size;
+    let row_step = fs + 4.0;
+
+    // the gradient runs from vmax (top) to vmin (bottom) with the mid color at the center
+    let center = fraction(vmax - cmap.center, vmax - vmin);
+    let mut out = format!(
+        "<defs><linearGradient id=\"colorbar\" x1=\"0\" y1=\"0\" x2=\"0\" y2=\"1\"><stop offset=\"0\" stop-color=\"{}\"/><stop offset=\"{:.4}\" stop-color=\"{}\"/><stop offset=\"1\" stop-color=\"{}\"/></linearGradient></defs>\n",
+        hex(cmap.color(vmax, vmin, vmax)),
+        center,
+        hex(cmap.color(cmap.center.clamp(vmin, vmax), vmin, vmax)),
+        hex(cmap.color(vmin, vmin, vmax))
+    );
+    out.push_str(&format!(
+        "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"15\" height=\"{:.2}\" fill=\"url(#colorbar)\" stroke=\"black\" stroke-width=\"0.5\"/>\n",
+        x, y, colorbar_height
+    ));
+
+    out.push_str(&format!("<g font-size=\"{}\" fill=\"black\">\n", fs));
+    let ticks = svg::ticks(vmin, vmax, 4);
+    for &tick in &ticks {
+        let ty = y + fraction(vmax - tick, vmax - vmin) * colorbar_height;
+        out.push_str(&format!(
+            "<line x1=\"{:.2}\" y1=\"{ty:.2}\" x2=\"{:.2}\" y2=\"{ty:.2}\" stroke=\"black\"/><text x=\"{:.2}\" y=\"{ty:.2}\" dominant-baseline=\"middle\">{}</text>\n",
+            x + 15.0,
+            x + 19.0,
+            x + 21.0,
+            svg::tick_label(tick, &ticks),
+            ty = ty
+        ));
+    }
+
+    let mut cy = y + colorbar_height + 20.0;
+    let annotations = style
+        .row_annotations
+        .iter()
+        .chain(style.col_annotations.iter());
+    for (ann, cats) in annotations.zip(categories) {
+        out.push_str(&format!(
+            "<text x=\"{:.2}\" y=\"{:.2}\" font-weight=\"bold\" dominant-baseline=\"middle\">{}</text>\n",
+            x,
+            cy,
+            svg::escape(&ann.name)
+        ));
+        cy += row_step;
+        for (cat, color) in cats {
+            out.push_str(&format!(
+                "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"10\" height=\"10\" fill=\"{}\"/><text x=\"{:.2}\" y=\"{:.2}\" dominant-baseline=\"middle\">{}</text>\n",
+                x,
+                cy - 5.0,
+                svg::escape(color),
+                x + 14.0,
+                cy,
+                svg::escape(cat)
+            ));
+            cy += row_step;
+        }
+        cy += 10.0;
+    }
+    out.push_str("</g>\n");
+
+    return out;
+}
+
+/// Distinct categories of an annotation (in order of appearance) with their colors
+fn category_colors(ann: &Annotation, palette: &[String]) -> Vec<(String, String)> {
+    let mut cats: Vec<(String, String)> = Vec::new();
+    for val in &ann.values {
+        if cats.iter().all(|(cat, _)| cat != val) {
+            let color = match palette.is_empty() {
+                true => "#808080".to_string(),
+                false => palette[cats.len() % palette.len()].clone(),
+            };
+            cats.push((val.clone(), color));
+        }
+    }
+
+    return cats;
+}
+
+fn lookup<'a>(colors: &'a [(String, String)], val: &str) -> &'a str {
+    return colors
+        .iter()
+        .find(|(cat, _)| cat == val)
+        .map(|(_, color)| color.as_str())
+        .unwrap_or("#808080");
+}
+
+pub(crate) fn resolve_labels(
+    labels: Option<&[String]>,
+    hierarchy: Option<&ClusterHierarchy>,
+    n: usize,
+    axis: &str,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    match labels.or(hierarchy.and_then(|h| h.labels())) {
+        Some(labels) if labels.len() != n => {
+            return Err(format!("Expected {} {} labels but got {}", n, axis, labels.len()).into())
+        }
+        Some(labels) => return Ok(labels.to_vec()),
+        None => return Ok((0..n).map(|i| i.to_string()).collect()),
+    }
+}
+
+pub(crate) fn leaf_order(hierarchy: Option<&ClusterHierarchy>, n: usize) -> Vec<usize> {
+    let order = hierarchy.map(|h| h.leaf_ordering()).unwrap_or_default();
+    if order.len() != n {
+        return (0..n).collect();
+    }
+
+    return order;
+}
+
+/// `num / denom` guarded against an empty range
+fn fraction(num: f64, denom: f64) -> f64 {
+    if denom <= 0.0 {
+        return 1.0;
+    }
+
+    return (num / denom).clamp(0.0, 1.0);
+}
+
+fn hex(rgb: [u8; 3]) -> String {
+    return format!("#{:02x}{:02x}{:02x}", rgb[0], rgb[1], rgb[2]);
+}
@@ -0,0 +1,402 @@
+use std::{error::Error, fs::File, io::Write};
+
+use serde::{Deserialize, Serialize};
+
+use super::svg;
+use crate::ClusterHierarchy;
+
+/// Side of the plot on which the root of the dendrogram is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Orientation {
+    /// root at the top, leaves along the bottom
+    Top,
+    /// root at the bottom, leaves along the top
+    Bottom,
+    /// root on the left, leaves along the right
+    Left,
+    /// root on the right, leaves along the left
+    Right,
+}
+
+/// Options for `ClusterHierarchy::dendrogram_svg`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DendrogramStyle {
+    pub orientation: Orientation,
+    /// svg width in pixels
+    pub width: f64,
+    /// svg height in pixels
+    pub height: f64,
+    /// draw the leaf labels (leaf indices for unlabelled hierarchies)
+    pub show_labels: bool,
+    pub font_size: f64,
+    /// draw an axis with the merge heights
+    pub show_axis: bool,
+    /// links below this height are colored per cluster, like SciPy's `color_threshold`.
+    /// `None` uses 70% of the highest merge, values `<= 0` draw everything in
+    /// `above_threshold_color`
+    pub color_threshold: Option<f64>,
+    /// colors cycled through for the clusters below the threshold
+    pub palette: Vec<String>,
+    pub above_threshold_color: String,
+    pub line_width: f64,
+}
+
+impl Default for DendrogramStyle {
+    fn default() -> Self {
+        DendrogramStyle {
+            orientation: Orientation::Top,
+            width: 800.0,
+            height: 500.0,
+            show_labels: true,
+            font_size: 10.0,
+            show_axis: true,
+            color_threshold: None,
+            palette: [
+                "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#7f7f7f",
+                "#bcbd22", "#17becf",
+            ]
+            .iter()
+            .map(|c| c.to_string())
+            .collect(),
+            above_threshold_color: "#1f77b4".to_string(),
+            line_width: 1.0,
+        }
+    }
+}
+
+/// Pixel rectangle the branches are drawn into
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Rect {
+    pub x0: f64,
+    pub y0: f64,
+    pub x1: f64,
+    pub y1: f64,
+}
+
+impl Rect {
+    /// Maps a position along the leaf axis and a height (both in `0..=1`) to pixels
+    pub(crate) fn point(&self, orientation: Orientation, pos: f64, height: f64) -> (f64, f64) {
+        let w = self.x1 - self.x0;
+        let h = self.y1 - self.y0;
+        match orientation {
+            Orientation::Top => return (self.x0 + pos * w, self.y1 - height * h),
+            Orientation::Bottom => return (self.x0 + pos * w, self.y0 + height * h),
+            Orientation::Left => return (self.x1 - height * w, self.y0 + pos * h),
+            Orientation::Right => return (self.x0 + height * w, self.y0 + pos * h),
+        }
+    }
+}
+
+impl ClusterHierarchy {
+    /// # Renders the hierarchy as an elbow style dendrogram in svg
+    ///
+    /// Leaves are placed in `leaf_ordering` order and every merge is drawn at its distance.
+    /// Labels come from the hierarchy labels (leaf indices when unlabelled).
+    ///
+    /// ### params:
+    /// - style: `DendrogramStyle` with the orientation, size, labels, axis and colors
+    pub fn dendrogram_svg(&self, style: &DendrogramStyle) -> String {
+        let n = self.leaf_size();
+        let (lo, hi) = height_range(self);
+        let ticks = svg::ticks(lo, hi, 5);
+
+        let char_width = style.font_size * 0.6;
+        let label_space = match style.show_labels {
+            true => {
+                let max_chars = (0..n)
+                    .map(|leaf| self.leaf_label(leaf).chars().count())
+                    .max()
+                    .unwrap_or(0);
+                max_chars as f64 * char_width + 6.0
+            }
+            false => 0.0,
+        };
+        let horizontal = matches!(style.orientation, Orientation::Top | Orientation::Bottom);
+        let axis_space = match (style.show_axis, horizontal) {
+            (false, _) => 0.0,
+            (true, true) => {
+                let max_chars = ticks
+                    .iter()
+                    .map(|&t| svg::tick_label(t, &ticks).len())
+                    .max()
+                    .unwrap_or(0);
+                max_chars as f64 * char_width + 14.0
+            }
+            (true, false) => style.font_size + 14.0,
+        };
+
+        let margin = 10.0;
+        let rect = match style.orientation {
+            Orientation::Top => Rect {
+                x0: margin + axis_space,
+                y0: margin,
+                x1: style.width - margin,
+                y1: style.height - margin - label_space,
+            },
+            Orientation::Bottom => Rect {
+                x0: margin + axis_space,
+                y0: margin + label_space,
+                x1: style.width - margin,
+                y1: style.height - margin,
+            },
+            Orientation::Left => Rect {
+                x0: margin,
+                y0: margin,
+                x1: style.width - margin - label_space,
+                y1: style.height - margin - axis_space,
+            },
+            Orientation::Right => Rect {
+                x0: margin + label_space,
+                y0: margin,
+                x1: style.width - margin,
+                y1: style.height - margin - axis_space,
+            },
+        };
+
+        let mut out = svg::open(style.width, style.height);
+        out.push_str(&branches_svg(self, rect, style));
+
+        if style.show_axis {
+            out.push_str(&axis_svg(&ticks, lo, hi, rect, style));
+        }
+
+        if style.show_labels {
+            out.push_str(&format!(
+                "<g font-size=\"{}\" fill=\"black\">\n",
+                style.font_size
+            ));
+            for (i, leaf) in leaf_order(self).into_iter().enumerate() {
+                let (x, y) = rect.point(style.orientation, (i as f64 + 0.5) / n as f64, 0.0);
+                let label = svg::escape(&self.leaf_label(leaf));
+                let text = match style.orientation {
+                    Orientation::Top => format!(
+                        "<text x=\"{x:.2}\" y=\"{y:.2}\" transform=\"rotate(90 {x:.2} {y:.2})\" dominant-baseline=\"middle\">{}</text>\n",
+                        label,
+                        x = x,
+                        y = y + 4.0
+                    ),
+                    Orientation::Bottom => format!(
+                        "<text x=\"{x:.2}\" y=\"{y:.2}\" transform=\"rotate(-90 {x:.2} {y:.2})\" dominant-baseline=\"middle\">{}</text>\n",
+                        label,
+                        x = x,
+                        y = y - 4.0
+                    ),
+                    Orientation::Left => format!(
+                        "<text x=\"{:.2}\" y=\"{:.2}\" dominant-baseline=\"middle\">{}</text>\n",
+                        x + 4.0,
+                        y,
+                        label
+                    ),
+                    Orientation::Right => format!(
+                        "<text x=\"{:.2}\" y=\"{:.2}\" text-anchor=\"end\" dominant-baseline=\"middle\">{}</text>\n",
+                        x - 4.0,
+                        y,
+                        label
+                    ),
+                };
+                out.push_str(&text);
+            }
+            out.push_str("</g>\n");
+        }
+
+        out.push_str("</svg>\n");
+        return out;
+    }
+
+    /// Writes the dendrogram svg to `fname`
+    pub fn write_dendrogram_svg(
+        &self,
+        fname: &str,
+        style: &DendrogramStyle,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut file = File::create(fname)?;
+        file.write_all(self.dendrogram_svg(style).as_bytes())?;
+
+        return Ok(());
+    }
+}
+
+/// Draws the links of `hierarchy` into `rect` (leaves evenly spaced along the leaf axis)
+pub(crate) fn branches_svg(
+    hierarchy: &ClusterHierarchy,
+    rect: Rect,
+    style: &DendrogramStyle,
+) -> String {
+    let n = hierarchy.leaf_size();
+    let linkage = hierarchy.linkage_matrix();
+    let (lo, hi) = height_range(hierarchy);
+    let scale = |dist: f64| (dist - lo) / (hi - lo);
+
+    // (position along the leaf axis, height) of every SciPy node id
+    let mut nodes = vec![(0.0, scale(0.0)); n + linkage.len()];
+    for (i, leaf) in leaf_order(hierarchy).into_iter().enumerate() {
+        nodes[leaf].0 = (i as f64 + 0.5) / n as f64;
+    }
+    for (i, row) in linkage.iter().enumerate() {
+        let (c1, c2) = (row[0] as usize, row[1] as usize);
+        nodes[n + i] = ((nodes[c1].0 + nodes[c2].0) / 2.0, scale(row[2]));
+    }
+
+    let colors = link_colors(&linkage, &nodes, n, style);
+
+    let mut out = format!("<g fill=\"none\" stroke-width=\"{}\">\n", style.line_width);
+    for (i, row) in linkage.iter().enumerate() {
+        let (c1, c2) = (row[0] as usize, row[1] as usize);
+        let top = nodes[n + i].1;
+        let points = [
+            rect.point(style.orientation, nodes[c1].0, nodes[c1].1),
+            rect.point(style.orientation, nodes[c1].0, top),
+            rect.point(style.orientation, nodes[c2].0, top),
+            rect.point(style.orientation, nodes[c2].0, nodes[c2].1),
+        ];
+        out.push_str(&format!(
+            "<path d=\"M{:.2},{:.2} L{:.2},{:.2} L{:.2},{:.2} L{:.2},{:.2}\" stroke=\"{}\"/>\n",
+            points[0].0,
+            points[0].1,
+            points[1].0,
+            points[1].1,
+            points[2].0,
+            points[2].1,
+            points[3].0,
+            points[3].1,
+            svg::escape(&colors[i])
+        ));
+    }
+    out.push_str("</g>\n");
+
+    return out;
+}
+
+/// Height axis along the side of `rect` opposite to the labels
+fn axis_svg(ticks: &[f64], lo: f64, hi: f64, rect: Rect, style: &DendrogramStyle) -> String {
+    let scale = |dist: f64| (dist - lo) / (hi - lo);
+    let horizontal = matches!(style.orientation, Orientation::Top | Orientation::Bottom);
+
+    let mut out = format!(
+        "<g stroke=\"black\" font-size=\"{}\" fill=\"black\">\n",
+        style.font_size
+    );
+    let (start, end) = (
+        rect.point(style.orientation, 0.0, 0.0),
+        rect.point(style.orientation, 0.0, 1.0),
+    );
+    match horizontal {
+        true => out.push_str(&format!(
+            "<line x1=\"{x:.2}\" y1=\"{:.2}\" x2=\"{x:.2}\" y2=\"{:.2}\"/>\n",
+            start.1,
+            end.1,
+            x = rect.x0 - 6.0
+        )),
+        false => out.push_str(&format!(
+            "<line x1=\"{:.2}\" y1=\"{y:.2}\" x2=\"{:.2}\" y2=\"{y:.2}\"/>\n",
+            start.0,
+            end.0,
+            y = rect.y1 + 6.0
+        )),
+    }
+
+    for &tick in ticks {
+        let (x, y) = rect.point(style.orientation, 0.0, scale(tick));
+        let label = svg::tick_label(tick, ticks);
+        match horizontal {
+            true => out.push_str(&format!(
+                "<line x1=\"{:.2}\" y1=\"{y:.2}\" x2=\"{:.2}\" y2=\"{y:.2}\"/><text x=\"{:.2}\" y=\"{y:.2}\" stroke=\"none\" text-anchor=\"end\" dominant-baseline=\"middle\">{}</text>\n",
+                rect.x0 - 10.0,
+                rect.x0 - 6.0,
+                rect.x0 - 12.0,
+                label,
+                y = y
+            )),
+            false => out.push_str(&format!(
+                "<line x1=\"{x:.2}\" y1=\"{:.2}\" x2=\"{x:.2}\" y2=\"{:.2}\"/><text x=\"{x:.2}\" y=\"{:.2}\" stroke=\"none\" text-anchor=\"middle\">{}</text>\n",
+                rect.y1 + 6.0,
+                rect.y1 + 10.0,
+                rect.y1 + 12.0 + style.font_size * 0.8,
+                label,
+                x = x
+            )),
+        }
+    }
+    out.push_str("</g>\n");
+
+    return out;
+}
+
+/// Colors every linkage row, links below the threshold get one palette color per cluster
+///
+/// The clusters are colored in leaf order, like SciPy.
+fn link_colors(
+    linkage: &[[f64; 4]],
+    nodes: &[(f64, f64)],
+    n: usize,
+    style: &DendrogramStyle,
+) -> Vec<String> {
+    let max_dist = linkage.last().map(|row| row[2]).unwrap_or(0.0);
+    let threshold = style.color_threshold.unwrap_or(0.7 * max_dist);
+
+    let mut colors = vec![style.above_threshold_color.clone(); linkage.len()];
+    if threshold <= 0.0 || style.palette.is_empty() {
+        return colors;
+    }
+
+    // the highest links below the threshold root one colored cluster each
+    let mut below_parent = vec![false; linkage.len()];
+    for row in linkage {
+        if row[2] >= threshold {
+            continue;
+        }
+        for &child in &row[..2] {
+            let child = child as usize;
+            if child >= n {
+                below_parent[child - n] = true;
+            }
+        }
+    }
+    let mut roots: Vec<usize> = (0..linkage.len())
+        .filter(|&i| linkage[i][2] < threshold && !below_parent[i])
+        .collect();
+    roots.sort_by(|&a, &b| nodes[n + a].0.total_cmp(&nodes[n + b].0));
+
+    let mut stack: Vec<(usize, usize)> = roots
+        .iter()
+        .enumerate()
+        .map(|(k, &root)| (root, k % style.palette.len()))
+        .collect();
+    while let Some((i, color)) = stack.pop() {
+        colors[i] = style.palette[color].clone();
+        for &child in &linkage[i][..2] {
+            let child = child as usize;
+            if child >= n {
+                stack.push((child - n, color));
+            }
+        }
+    }
+
+    return colors;
+}
+
+/// Leaf ordering that falls back to the leaf ids for hierarchies without a tree
+fn leaf_order(hierarchy: &ClusterHierarchy) -> Vec<usize> {
+    let order = hierarchy.leaf_ordering();
+    if order.len() != hierarchy.leaf_size() {
+        return (0..hierarchy.leaf_size()).collect();
+    }
+
+    return order;
+}
+
+/// Range of merge heights covered by the height axis
+fn height_range(hierarchy: &ClusterHierarchy) -> (f64, f64) {
+    let dists: Vec<f64> = hierarchy
+        .linkage_matrix()
+        .iter()
+        .map(|row| row[2])
+        .collect();
+    let lo = dists.iter().cloned().fold(0.0, f64::min);
+    let hi = dists.iter().cloned().fold(lo, f64::max);
+    if hi <= lo {
+        return (lo, lo + 1.0);
+    }
+
+    return (lo, hi);
+}
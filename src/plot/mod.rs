@@ -0,0 +1,10 @@
+pub(crate) mod clustermap;
+mod dendrogram;
+pub(crate) mod svg;
+mod tanglegram;
+
+#[cfg(feature = "png")]
+pub use clustermap::write_heatmap_png;
+pub use clustermap::{heatmap_svg, write_heatmap_svg, Annotation, ColorMap, HeatmapStyle};
+pub use dendrogram::{DendrogramStyle, Orientation};
+pub use tanglegram::TanglegramStyle;
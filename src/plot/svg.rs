@@ -0,0 +1,96 @@
+/// Escapes text for use inside svg/xml content and attribute values
+pub(crate) fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(c),
+        }
+    }
+
+    return out;
+}
+
+/// Opening `<svg>` tag with a white background
+pub(crate) fn open(width: f64, height: f64) -> String {
+    return format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\">\n<rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n",
+        w = width,
+        h = height
+    );
+}
+
+/// Evenly spaced "nice" tick values (steps of 1, 2 or 5 times a power of ten) covering `lo..=hi`
+pub(crate) fn ticks(lo: f64, hi: f64, target: usize) -> Vec<f64> {
+    let span = hi - lo;
+    if span <= 0.0 || !span.is_finite() {
+        return vec![lo];
+    }
+
+    let raw = span / target.max(1) as f64;
+    let magnitude = 10f64.powf(raw.log10().floor());
+    let step = [1.0, 2.0, 5.0, 10.0]
+        .iter()
+        .map(|m| m * magnitude)
+        .find(|&step| step >= raw)
+        .unwrap_or(10.0 * magnitude);
+
+    let first = (lo / step).ceil() as i64;
+    let last = (hi / step + 1e-9).floor() as i64;
+    return (first..=last).map(|i| i as f64 * step).collect();
+}
+
+/// Formats a tick value with just enough decimals for the tick step
+pub(crate) fn tick_label(val: f64, ticks: &[f64]) -> String {
+    let step = match ticks {
+        [a, b, ..] => (b - a).abs(),
+        _ => 1.0,
+    };
+    let decimals = (-step.log10().floor()).max(0.0) as usize;
+
+    return format!("{:.*}", decimals, val);
+}
+
+/// Rasterizes an svg document to a png file, text is drawn with the system fonts
+#[cfg(feature = "png")]
+pub(crate) fn write_png(svg: &str, fname: &str) -> Result<(), Box<dyn std::error::Error>> {
+    use resvg::{tiny_skia, usvg, usvg::fontdb};
+
+    let mut opt = usvg::Options::default();
+    let db = opt.fontdb_mut();
+    db.load_system_fonts();
+
+    // fall back to any installed sans face when the default (Arial) is missing
+    let query = fontdb::Query {
+        families: &[fontdb::Family::SansSerif],
+        ..Default::default()
+    };
+    if db.query(&query).is_none() {
+        let fallback = db
+            .faces()
+            .flat_map(|face| face.families.iter().map(|(name, _)| name.clone()))
+            .find(|name| name.contains("Sans") && !name.contains("Mono"))
+            .or_else(|| {
+                db.faces()
+                    .next()
+                    .and_then(|f| f.families.first())
+                    .map(|f| f.0.clone())
+            });
+        if let Some(family) = fallback {
+            db.set_sans_serif_family(family);
+        }
+    }
+
+    let tree = usvg::Tree::from_str(svg, &opt)?;
+    let size = tree.size().to_int_size();
+    let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height())
+        .ok_or("Image size must be greater than zero")?;
+    resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+    pixmap.save_png(fname)?;
+
+    return Ok(());
+}
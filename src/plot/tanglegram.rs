@@ -0,0 +1,184 @@
+use std::{error::Error, fs::File, io::Write};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    dendrogram::{branches_svg, Rect},
+    svg, DendrogramStyle, Orientation,
+};
+use crate::{ClusterHierarchy, Tanglegram};
+
+/// Options for `Tanglegram::to_svg`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TanglegramStyle {
+    /// svg width in pixels
+    pub width: f64,
+    /// svg height in pixels
+    pub height: f64,
+    /// width in pixels of each dendrogram
+    pub dendrogram_width: f64,
+    /// branch colors and line width of both dendrograms (the orientation is ignored)
+    pub dendrogram: DendrogramStyle,
+    pub show_labels: bool,
+    pub font_size: f64,
+    pub link_color: String,
+    pub link_width: f64,
+    /// color the lines by the left tree cut into this many flat clusters, with the
+    /// dendrogram palette
+    pub link_clusters: Option<usize>,
+}
+
+impl Default for TanglegramStyle {
+    fn default() -> Self {
+        TanglegramStyle {
+            width: 900.0,
+            height: 600.0,
+            dendrogram_width: 250.0,
+            dendrogram: DendrogramStyle {
+                color_threshold: Some(0.0),
+                above_threshold_color: "#000000".to_string(),
+                ..Default::default()
+            },
+            show_labels: true,
+            font_size: 10.0,
+            link_color: "#888888".to_string(),
+            link_width: 1.0,
+            link_clusters: None,
+        }
+    }
+}
+
+impl Tanglegram {
+    /// # Renders the tanglegram in svg
+    ///
+    /// The left tree has its root on the left and the right tree on the right, with the leaf
+    /// labels facing each other and a line between the two positions of every shared leaf.
+    ///
+    /// ### params:
+    /// - style: `TanglegramStyle` with the size, dendrogram colors, labels and line colors
+    pub fn to_svg(&self, style: &TanglegramStyle) -> Result<String, Box<dyn Error>> {
+        let link_colors: Vec<String> = match style.link_clusters {
+            Some(k) => {
+                if style.dendrogram.palette.is_empty() {
+                    return Err("Coloring the lines needs a non-empty palette".into());
+                }
+                let clusters = self.left.flat_clusters(k)?;
+                self.links
+                    .iter()
+                    .map(|&(leaf, _)| {
+                        let color = (clusters[leaf] - 1) % style.dendrogram.palette.len();
+                        style.dendrogram.palette[color].clone()
+                    })
+                    .collect()
+            }
+            None => vec![style.link_color.clone(); self.links.len()],
+        };
+
+        let margin = 10.0;
+        let label_space = |hierarchy: &ClusterHierarchy| match style.show_labels {
+            true => {
+                let max_chars = (0..hierarchy.leaf_size())
+                    .map(|leaf| hierarchy.leaf_label(leaf).chars().count())
+                    .max()
+                    .unwrap_or(0);
+                max_chars as f64 * style.font_size * 0.6 + 8.0
+            }
+            false => 0.0,
+        };
+
+        let left_rect = Rect {
+            x0: margin,
+            y0: margin,
+            x1: margin + style.dendrogram_width,
+            y1: style.height - margin,
+        };
+        let right_rect = Rect {
+            x0: style.width - margin - style.dendrogram_width,
+            y0: margin,
+            x1: style.width - margin,
+            y1: style.height - margin,
+        };
+        let link_start = left_rect.x1 + label_space(&self.left);
+        let link_end = right_rect.x0 - label_space(&self.right);
+
+        let mut out = svg::open(style.width, style.height);
+        let sides = [
+            (&self.left, left_rect, Orientation::Left),
+            (&self.right, right_rect, Orientation::Right),
+        ];
+        for (hierarchy, rect, orientation) in sides {
+            let dend_style = DendrogramStyle {
+                orientation,
+                ..style.dendrogram.clone()
+            };
+            out.push_str(&branches_svg(hierarchy, rect, &dend_style));
+        }
+
+        // y of every leaf on both sides
+        let leaf_y = |hierarchy: &ClusterHierarchy, rect: Rect| {
+            let n = hierarchy.leaf_size();
+            let mut ys = vec![0.0; n];
+            for (i, leaf) in hierarchy.leaf_ordering().into_iter().enumerate() {
+                ys[leaf] = rect
+                    .point(Orientation::Left, (i as f64 + 0.5) / n as f64, 0.0)
+                    .1;
+            }
+            return ys;
+        };
+        let left_y = leaf_y(&self.left, left_rect);
+        let right_y = leaf_y(&self.right, right_rect);
+
+        out.push_str(&format!(
+            "<g fill=\"none\" stroke-width=\"{}\">\n",
+            style.link_width
+        ));
+        for (&(left, right), color) in self.links.iter().zip(&link_colors) {
+            out.push_str(&format!(
+                "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke=\"{}\"/>\n",
+                link_start,
+                left_y[left],
+                link_end,
+                right_y[right],
+                svg::escape(color)
+            ));
+        }
+        out.push_str("</g>\n");
+
+        if style.show_labels {
+            out.push_str(&format!(
+                "<g font-size=\"{}\" fill=\"black\">\n",
+                style.font_size
+            ));
+            for (leaf, y) in left_y.iter().enumerate() {
+                out.push_str(&format!(
+                    "<text x=\"{:.2}\" y=\"{:.2}\" dominant-baseline=\"middle\">{}</text>\n",
+                    left_rect.x1 + 4.0,
+                    y,
+                    svg::escape(&self.left.leaf_label(leaf))
+                ));
+            }
+            for (leaf, y) in right_y.iter().enumerate() {
+                out.push_str(&format!(
+                    "<text x=\"{:.2}\" y=\"{:.2}\" text-anchor=\"end\" dominant-baseline=\"middle\">{}</text>\n",
+                    right_rect.x0 - 4.0,
+                    y,
+                    svg::escape(&self.right.leaf_label(leaf))
+                ));
+            }
+            out.push_str("</g>\n");
+        }
+
+        out.push_str("</svg>\n");
+        return Ok(out);
+    }
+
+    /// Writes the tanglegram svg to `fname`
+    pub fn write_svg(&self, fname: &str, style: &TanglegramStyle) -> Result<(), Box<dyn Error>> {
+        let svg = self.to_svg(style)?;
+
+        let mut file = File::create(fname)?;
+        file.write_all(svg.as_bytes())?;
+
+        return Ok(());
+    }
+}
@@ -0,0 +1,35 @@
+use std::path::{Path, PathBuf};
+
+use polars::prelude::*;
+
+/// # Lazily scans one or many Parquet files as a single frame
+///
+/// Files are concatenated vertically in the given order and must share a schema. Nothing is
+/// read until the frame is collected, so filters and column selections are pushed down.
+pub fn scan_parquet_files<P: AsRef<Path>>(paths: &[P]) -> PolarsResult<LazyFrame> {
+    if paths.is_empty() {
+        polars_bail!(ComputeError: "no parquet files given");
+    }
+
+    let paths: Vec<PathBuf> = paths.iter().map(|p| p.as_ref().to_path_buf()).collect();
+    return LazyFrame::scan_parquet_files(paths.into(), ScanArgsParquet::default());
+}
+
+/// # Lazily scans one or many delimited text files (csv, tsv) as a single frame
+///
+/// Every file needs a header row and all files must share a schema.
+///
+/// ### params:
+/// - paths: files, concatenated in order
+/// - separator: field delimiter, e.g. `b','` or `b'\t'`
+pub fn scan_csv_files<P: AsRef<Path>>(paths: &[P], separator: u8) -> PolarsResult<LazyFrame> {
+    if paths.is_empty() {
+        polars_bail!(ComputeError: "no csv files given");
+    }
+
+    let paths: Vec<PathBuf> = paths.iter().map(|p| p.as_ref().to_path_buf()).collect();
+    return LazyCsvReader::new_paths(paths.into())
+        .with_separator(separator)
+        .with_has_header(true)
+        .finish();
+}
@@ -0,0 +1,123 @@
+#![cfg(feature = "cli")]
+
+use std::process::Command;
+
+fn tmp_path(fname: &str) -> String {
+    std::env::temp_dir()
+        .join(format!("cp_hc_cli_{}_{}", std::process::id(), fname))
+        .to_string_lossy()
+        .to_string()
+}
+
+#[test]
+fn cli_test_both_axes() {
+    let input = tmp_path("plate.csv");
+    let mut csv = String::from("Metadata_Well,f1,f2,f3,f4,f5\n");
+    for i in 0..8 {
+        let vals: Vec<String> = (0..5)
+            .map(|j| (((i * 7 + j * 3) % 11) as f64 + 0.1 * j as f64).to_string())
+            .collect();
+        csv.push_str(&format!("W{},{}\n", i, vals.join(",")));
+    }
+    std::fs::write(&input, csv).unwrap();
+
+    let tree = tmp_path("tree.json");
+    let clusters = tmp_path("clusters.csv");
+    let status = Command::new(env!("CARGO_BIN_EXE_cp-cluster"))
+        .args([
+            input.as_str(),
+            "--metric",
+            "pearson",
+            "--linkage",
+            "complete",
+            "--exclude-regex",
+            "^Metadata_",
+            "--label-col",
+            "Metadata_Well",
+            "--axis",
+            "both",
+            "--tree",
+            tree.as_str(),
+            "--clusters",
+            clusters.as_str(),
+            "-k",
+            "2",
+        ])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let row_clusters = std::fs::read_to_string(tmp_path("clusters_rows.csv")).unwrap();
+    let lines: Vec<&str> = row_clusters.lines().collect();
+    assert_eq!(lines[0], "leaf,label,cluster");
+    assert_eq!(lines.len(), 9);
+    assert!(lines[1].starts_with("0,W0,"));
+
+    let col_clusters = std::fs::read_to_string(tmp_path("clusters_cols.csv")).unwrap();
+    assert_eq!(col_clusters.lines().count(), 6);
+    assert!(col_clusters.contains(",f1,"));
+
+    for fname in ["tree_rows.json", "tree_cols.json"] {
+        let json = std::fs::read_to_string(tmp_path(fname)).unwrap();
+        assert!(serde_json::from_str::<serde_json::Value>(&json).is_ok());
+    }
+
+    let status = Command::new(env!("CARGO_BIN_EXE_cp-cluster"))
+        .args([input.as_str(), "--clusters", clusters.as_str()])
+        .status()
+        .unwrap();
+    assert!(!status.success());
+
+    for fname in [
+        "plate.csv",
+        "clusters_rows.csv",
+        "clusters_cols.csv",
+        "tree_rows.json",
+        "tree_cols.json",
+    ] {
+        _ = std::fs::remove_file(tmp_path(fname));
+    }
+}
+
+#[test]
+fn cli_test_deep_tree() {
+    // rows on a circle with growing gaps: single linkage merges them into one long chain
+    let n = 1500;
+    let input = tmp_path("chain.csv");
+    let mut csv = String::from("f1,f2,f3\n");
+    let mut angle = 0.0;
+    for i in 0..n {
+        angle += 1e-4 * (1.0 + i as f64 / n as f64);
+        let (x, y) = (angle.cos(), angle.sin());
+        let vals = [
+            x / 2f64.sqrt() + y / 6f64.sqrt(),
+            -x / 2f64.sqrt() + y / 6f64.sqrt(),
+            -2.0 * y / 6f64.sqrt(),
+        ];
+        csv.push_str(&format!("{},{},{}\n", vals[0], vals[1], vals[2]));
+    }
+    std::fs::write(&input, csv).unwrap();
+
+    let tree = tmp_path("chain_tree.json");
+    let status = Command::new(env!("CARGO_BIN_EXE_cp-cluster"))
+        .args([
+            input.as_str(),
+            "--linkage",
+            "single",
+            "--tree",
+            tree.as_str(),
+        ])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    // compact by default: linear in the number of nodes, the pretty form would be ~30 MB
+    let size = std::fs::metadata(&tree).unwrap().len();
+    assert!(size < 500_000, "tree json is {} bytes", size);
+    let loaded = cp_hierarchical_clustering::ClusterHierarchy::load_tree(&tree).unwrap();
+    assert_eq!(loaded.leaf_size(), n);
+    assert_eq!(loaded.tree_view().depth(0), n - 1);
+
+    _ = std::fs::remove_file(input);
+    _ = std::fs::remove_file(tree);
+}
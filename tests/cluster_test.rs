@@ -1,5 +1,10 @@
 use cp_hierarchical_clustering::{
-    calculate_matrix, create_hierarchy_from_df, hierarchical_clustering, LinkageMethod, Metric,
+    bakers_gamma, calculate_matrix, cophenetic_correlation, create_biclustered_heatmap_from_df,
+    create_hierarchy, create_hierarchy_from_df, create_hierarchy_from_df_with_spec,
+    create_hierarchy_from_lazy, create_hierarchy_from_parquet, create_labeled_hierarchy_from_df,
+    df_to_matrix, fowlkes_mallows, fowlkes_mallows_curve, hierarchical_clustering, lazy_to_matrix,
+    robinson_foulds, ClusterHierarchy, ColumnSpec, DendrogramNode, LinkageMethod, Metric,
+    WeightAggregation,
 };
 
 use polars::prelude::*;
@@ -31,7 +36,7 @@ fn matrix_to_df(mat: &[Vec<f64>]) -> PolarsResult<DataFrame> {
 
 #[test]
 fn cluster_test() {
-    _ = ThreadPoolBuilder::new()
+    ThreadPoolBuilder::new()
         .num_threads(num_cpus::get().saturating_sub(10))
         .build_global()
         .unwrap();
@@ -86,6 +91,59 @@ fn cluster_test_df() {
     println!("{:?}", res.leaf_ordering());
 }
 
+#[test]
+fn cluster_test_df_dtypes() {
+    let ints = df!(
+        "well" => ["A1", "A2", "A3", "A4", "A5"],
+        "area" => [1i64, 4, 9, 20, 22],
+        "count" => [3i32, 1, 7, 2, 8],
+        "intensity" => [5i64, 2, 6, 11, 3],
+        "texture" => [2i32, 9, 4, 6, 1],
+    )
+    .unwrap();
+    let floats = df!(
+        "area" => [1.0, 4.0, 9.0, 20.0, 22.0],
+        "count" => [3.0, 1.0, 7.0, 2.0, 8.0],
+        "intensity" => [5.0, 2.0, 6.0, 11.0, 3.0],
+        "texture" => [2.0, 9.0, 4.0, 6.0, 1.0],
+    )
+    .unwrap();
+
+    // integer columns are cast to f64, the ignored string column is never read
+    let res = create_hierarchy_from_df(
+        &ints,
+        Metric::Distance,
+        LinkageMethod::Average,
+        &Some(vec![0]),
+    )
+    .unwrap();
+    let expected =
+        create_hierarchy_from_df(&floats, Metric::Distance, LinkageMethod::Average, &None).unwrap();
+    assert_eq!(res.linkage_matrix(), expected.linkage_matrix());
+
+    // a null anywhere in a feature column fails the call, unless that column is ignored
+    let mut nulls = floats.clone();
+    nulls
+        .replace(
+            "area",
+            Series::new(
+                "area".into(),
+                [Some(1.0), Some(4.0), None, Some(20.0), Some(22.0)],
+            ),
+        )
+        .unwrap();
+    assert!(
+        create_hierarchy_from_df(&nulls, Metric::Distance, LinkageMethod::Average, &None).is_err()
+    );
+    assert!(create_hierarchy_from_df(
+        &nulls,
+        Metric::Distance,
+        LinkageMethod::Average,
+        &Some(vec![0])
+    )
+    .is_ok());
+}
+
 #[test]
 fn cluster_test_cluster_struct() {
     // _ = ThreadPoolBuilder::new()
@@ -105,3 +163,532 @@ fn cluster_test_cluster_struct() {
 
     println!("{:?}", res.get_raw_nodes().unwrap());
 }
+
+fn order_cost(order: &[usize], dist: &[Vec<f64>]) -> f64 {
+    order.windows(2).map(|w| dist[w[0]][w[1]]).sum()
+}
+
+fn all_orderings(node: &DendrogramNode) -> Vec<Vec<usize>> {
+    match (&node.left, &node.right) {
+        (Some(left), Some(right)) => {
+            let lefts = all_orderings(left);
+            let rights = all_orderings(right);
+            let mut orders = Vec::new();
+            for l in &lefts {
+                for r in &rights {
+                    orders.push([l.clone(), r.clone()].concat());
+                    orders.push([r.clone(), l.clone()].concat());
+                }
+            }
+            orders
+        }
+        _ => vec![vec![node.cid]],
+    }
+}
+
+fn node_heights(node: &DendrogramNode) -> Vec<f64> {
+    let mut heights = vec![node.distance];
+    if let Some(ref left) = node.left {
+        heights.extend(node_heights(left));
+    }
+    if let Some(ref right) = node.right {
+        heights.extend(node_heights(right));
+    }
+    heights.sort_by(f64::total_cmp);
+    heights
+}
+
+#[test]
+fn cluster_test_optimal_leaf_ordering() {
+    let matrix = rand_matrix(9, 50);
+    let dist = calculate_matrix(&matrix, Metric::Pearson, true);
+
+    let res = hierarchical_clustering(&dist, LinkageMethod::Average).unwrap();
+    let olo = res.optimal_leaf_ordering(&dist).unwrap();
+
+    let tree = res.get_raw_nodes().unwrap();
+    let brute_min = all_orderings(&tree)
+        .iter()
+        .map(|order| order_cost(order, &dist))
+        .fold(f64::MAX, f64::min);
+
+    let olo_cost = order_cost(&olo.leaf_ordering(), &dist);
+    assert!((olo_cost - brute_min).abs() < 1e-9);
+    assert!(olo_cost <= order_cost(&res.leaf_ordering(), &dist) + 1e-9);
+
+    let olo_tree = olo.get_raw_nodes().unwrap();
+    assert_eq!(node_heights(&tree), node_heights(&olo_tree));
+
+    let mut leaves = olo.leaf_ordering();
+    leaves.sort();
+    assert_eq!(leaves, (0..9).collect::<Vec<usize>>());
+}
+
+fn subtree_leaves(node: &DendrogramNode) -> Vec<usize> {
+    match (&node.left, &node.right) {
+        (Some(left), Some(right)) => [subtree_leaves(left), subtree_leaves(right)].concat(),
+        _ => vec![node.cid],
+    }
+}
+
+fn assert_sorted_by_weight(node: &DendrogramNode, weights: &[f64]) {
+    if let (Some(left), Some(right)) = (&node.left, &node.right) {
+        let mean = |leaves: Vec<usize>| {
+            leaves.iter().map(|&i| weights[i]).sum::<f64>() / leaves.len() as f64
+        };
+        assert!(mean(subtree_leaves(left)) <= mean(subtree_leaves(right)));
+        assert_sorted_by_weight(left, weights);
+        assert_sorted_by_weight(right, weights);
+    }
+}
+
+#[test]
+fn cluster_test_reorder_by_weights() {
+    let matrix = rand_matrix(30, 50);
+    let dist = calculate_matrix(&matrix, Metric::Pearson, true);
+    let weights: Vec<f64> = matrix.iter().map(|row| row[0]).collect();
+
+    let res = hierarchical_clustering(&dist, LinkageMethod::Complete).unwrap();
+    let reordered = res
+        .reorder_by_weights(&weights, WeightAggregation::Mean)
+        .unwrap();
+
+    assert_sorted_by_weight(&reordered.get_raw_nodes().unwrap(), &weights);
+    assert_eq!(
+        reordered.leaf_ordering(),
+        subtree_leaves(&reordered.get_raw_nodes().unwrap())
+    );
+    assert!(res
+        .reorder_by_weights(&weights[1..], WeightAggregation::Sum)
+        .is_err());
+}
+
+#[test]
+fn cluster_test_biclustered_heatmap() {
+    let matrix = rand_matrix(40, 12);
+    let df = matrix_to_df(&matrix).unwrap();
+
+    let res = create_biclustered_heatmap_from_df(
+        &df,
+        Metric::Pearson,
+        LinkageMethod::Average,
+        Metric::Distance,
+        LinkageMethod::Complete,
+        &None,
+        None,
+    )
+    .unwrap();
+
+    assert_eq!(res.row_hierarchy().leaf_size(), 40);
+    assert_eq!(res.col_hierarchy().labels().unwrap()[3], "Col: 3");
+    assert_eq!(res.col_hierarchy().leaf_size(), 12);
+
+    let reordered = res.reordered_matrix();
+    for (i, &r) in res.row_order().iter().enumerate() {
+        for (j, &c) in res.col_order().iter().enumerate() {
+            assert_eq!(reordered[i][j], matrix[r][c]);
+        }
+    }
+}
+
+#[test]
+fn cluster_test_label_col() {
+    let matrix = rand_matrix(30, 20);
+    let mut df = matrix_to_df(&matrix).unwrap();
+    let wells: Vec<String> = (0..30).map(|i| format!("A{:02}", i)).collect();
+    df.with_column(Series::new("Metadata_Well".into(), wells.clone()))
+        .unwrap();
+
+    let res = create_labeled_hierarchy_from_df(
+        &df,
+        Metric::Pearson,
+        LinkageMethod::Average,
+        &None,
+        "Metadata_Well",
+    )
+    .unwrap();
+
+    assert_eq!(res.labels().unwrap(), wells);
+    assert_eq!(res.leaf_label_ordering().len(), 30);
+}
+
+#[test]
+fn cluster_test_column_spec() {
+    let matrix = rand_matrix(25, 10);
+    let mut df = matrix_to_df(&matrix).unwrap();
+    let plates: Vec<i64> = (0..25).map(|i| i % 3).collect();
+    let counts: Vec<i32> = (0..25).map(|i| i * 7 % 11).collect();
+    let wells: Vec<String> = (0..25).map(|i| format!("B{:02}", i)).collect();
+    df.with_column(Series::new("Metadata_Plate".into(), plates))
+        .unwrap();
+    df.with_column(Series::new("Metadata_Well".into(), wells.clone()))
+        .unwrap();
+    df.with_column(Series::new("Cell_Count".into(), counts))
+        .unwrap();
+
+    // non-numeric metadata is skipped, the i32 count column is cast to f64
+    let all = df_to_matrix(&df, &ColumnSpec::all_numeric(), None).unwrap();
+    assert_eq!(all.feature_names.len(), 12);
+    assert_eq!(all.data[3][11], (3 * 7 % 11) as f64);
+
+    let spec = ColumnSpec::exclude_regex("^Metadata_");
+    let features = df_to_matrix(&df, &spec, Some("Metadata_Well")).unwrap();
+    assert_eq!(features.feature_names.len(), 11);
+    assert!(features
+        .feature_names
+        .iter()
+        .all(|n| !n.starts_with("Metadata_")));
+    assert_eq!(features.labels.unwrap(), wells);
+
+    let by_name = ColumnSpec::names(&["Col: 2", "Cell_Count"]);
+    let named = df_to_matrix(&df, &by_name, None).unwrap();
+    assert_eq!(named.feature_names, vec!["Col: 2", "Cell_Count"]);
+    assert_eq!(named.data[5][0], matrix[5][2]);
+
+    assert!(df_to_matrix(&df, &ColumnSpec::names(&["Metadata_Well"]), None).is_err());
+    assert!(df_to_matrix(&df, &ColumnSpec::names(&["missing"]), None).is_err());
+    assert!(df_to_matrix(&df, &ColumnSpec::exclude_regex("("), None).is_err());
+
+    let res = create_hierarchy_from_df_with_spec(
+        &df,
+        Metric::Pearson,
+        LinkageMethod::Average,
+        &spec,
+        Some("Metadata_Well"),
+    )
+    .unwrap();
+    assert_eq!(res.labels().unwrap(), wells);
+}
+
+#[test]
+fn cluster_test_lazy_frame() {
+    let matrix = rand_matrix(60, 30);
+    let mut df = matrix_to_df(&matrix).unwrap();
+    let plates: Vec<i64> = (0..60).map(|i| i % 3).collect();
+    let wells: Vec<String> = (0..60).map(|i| format!("C{:02}", i)).collect();
+    df.with_column(Series::new("Metadata_Plate".into(), plates))
+        .unwrap();
+    df.with_column(Series::new("Metadata_Well".into(), wells))
+        .unwrap();
+
+    let lf = df.clone().lazy().filter(col("Metadata_Plate").eq(lit(1)));
+    let features =
+        lazy_to_matrix(lf.clone(), &ColumnSpec::exclude_regex("^Metadata_"), None).unwrap();
+    assert_eq!(features.data.len(), 20);
+    assert_eq!(features.data[0], matrix[1]);
+
+    let res = create_hierarchy_from_lazy(
+        lf,
+        Metric::Pearson,
+        LinkageMethod::Average,
+        &ColumnSpec::exclude_regex("^Metadata_"),
+        Some("Metadata_Well"),
+    )
+    .unwrap();
+    assert_eq!(res.leaf_size(), 20);
+    assert_eq!(res.labels().unwrap()[0], "C01");
+
+    // nulls are reported instead of silently dropped
+    let nulls = df
+        .lazy()
+        .with_column(lit(NULL).cast(DataType::Float64).alias("Empty"))
+        .collect()
+        .unwrap();
+    assert!(create_hierarchy_from_df(
+        &nulls,
+        Metric::Pearson,
+        LinkageMethod::Average,
+        &Some(vec![30, 31])
+    )
+    .is_err());
+}
+
+#[test]
+fn cluster_test_parquet() {
+    let dir = std::env::temp_dir();
+    let mut paths: Vec<String> = Vec::new();
+    let mut wells: Vec<String> = Vec::new();
+
+    for plate in 0..2 {
+        let matrix = rand_matrix(15, 40);
+        let mut df = matrix_to_df(&matrix).unwrap();
+        let plate_wells: Vec<String> = (0..15).map(|i| format!("P{}_{:02}", plate, i)).collect();
+        df.with_column(Series::new("Metadata_Well".into(), plate_wells.clone()))
+            .unwrap();
+        wells.extend(plate_wells);
+
+        let path = dir
+            .join(format!(
+                "cp_hc_{}_plate{}.parquet",
+                std::process::id(),
+                plate
+            ))
+            .to_string_lossy()
+            .to_string();
+        let mut file = std::fs::File::create(&path).unwrap();
+        ParquetWriter::new(&mut file).finish(&mut df).unwrap();
+        paths.push(path);
+    }
+
+    let res = create_hierarchy_from_parquet(
+        &paths,
+        Metric::Pearson,
+        LinkageMethod::Average,
+        &ColumnSpec::exclude_regex("^Metadata_"),
+        Some("Metadata_Well"),
+    )
+    .unwrap();
+
+    assert_eq!(res.leaf_size(), 30);
+    assert_eq!(res.labels().unwrap(), wells);
+
+    for path in paths {
+        _ = std::fs::remove_file(path);
+    }
+}
+
+#[test]
+fn cluster_test_flat_clusters() {
+    let n = 20;
+    let matrix = rand_matrix(n, 50);
+    let res = create_hierarchy(&matrix, Metric::Pearson, LinkageMethod::Average).unwrap();
+    let order = res.leaf_ordering();
+    let linkage = res.linkage_matrix();
+
+    for k in 1..=n {
+        let clusters = res.flat_clusters(k).unwrap();
+        assert_eq!(clusters.len(), n);
+
+        // clusters are contiguous in leaf order and numbered 1..=k
+        let in_order: Vec<usize> = order.iter().map(|&leaf| clusters[leaf]).collect();
+        assert_eq!(in_order[0], 1);
+        assert!(in_order
+            .windows(2)
+            .all(|w| w[1] == w[0] || w[1] == w[0] + 1));
+        assert_eq!(*in_order.last().unwrap(), k);
+
+        if k < n {
+            let height = linkage[n - k - 1][2];
+            assert_eq!(res.flat_clusters_at_height(height), clusters);
+        }
+    }
+
+    assert!(res.flat_clusters(0).is_err());
+    assert!(res.flat_clusters(n + 1).is_err());
+    assert!(res
+        .flat_clusters_at_height(f64::MAX)
+        .iter()
+        .all(|&c| c == 1));
+}
+
+#[test]
+fn cluster_test_tree_view() {
+    // ((0, 1)5, (2, (3, 4)6)7)8
+    let linkage = [
+        [0.0, 1.0, 0.1, 2.0],
+        [3.0, 4.0, 0.2, 2.0],
+        [2.0, 6.0, 0.5, 3.0],
+        [5.0, 7.0, 0.9, 5.0],
+    ];
+    let res = ClusterHierarchy::from_linkage_matrix(&linkage).unwrap();
+    let view = res.tree_view();
+
+    assert_eq!(view.root(), 8);
+    assert_eq!(view.num_nodes(), 9);
+    assert_eq!(view.parent(3), Some(6));
+    assert_eq!(view.parent(8), None);
+    assert_eq!(view.children(7), Some((2, 6)));
+    assert!(view.is_leaf(4) && !view.is_leaf(5));
+    assert_eq!(view.depth(4), 3);
+    assert_eq!(view.height(7), 0.5);
+    assert_eq!(view.size(7), 3);
+    assert_eq!(view.leaves(7), &[2, 3, 4]);
+    assert_eq!(view.leaf_order(), res.leaf_ordering().as_slice());
+
+    assert_eq!(view.lca(3, 4), 6);
+    assert_eq!(view.lca(2, 4), 7);
+    assert_eq!(view.lca(0, 4), 8);
+    assert_eq!(view.lca(6, 3), 6);
+    assert_eq!(view.lca(1, 1), 1);
+    assert!(view.is_ancestor(7, 3) && !view.is_ancestor(5, 3) && view.is_ancestor(3, 3));
+
+    assert_eq!(
+        view.preorder().collect::<Vec<_>>(),
+        vec![8, 5, 0, 1, 7, 2, 6, 3, 4]
+    );
+    assert_eq!(
+        view.postorder().collect::<Vec<_>>(),
+        vec![0, 1, 5, 2, 3, 4, 6, 7, 8]
+    );
+    assert_eq!(
+        view.level_order().collect::<Vec<_>>(),
+        vec![8, 5, 7, 0, 1, 2, 6, 3, 4]
+    );
+
+    // lca agrees with walking parent links on a clustered tree
+    let res = create_hierarchy(
+        &rand_matrix(40, 10),
+        Metric::Distance,
+        LinkageMethod::Single,
+    )
+    .unwrap();
+    let view = res.tree_view();
+    let ancestors = |mut node: usize| {
+        let mut path = vec![node];
+        while let Some(parent) = view.parent(node) {
+            path.push(parent);
+            node = parent;
+        }
+        path
+    };
+    for a in 0..40 {
+        for b in 0..40 {
+            let path_b = ancestors(b);
+            let expected = ancestors(a)
+                .into_iter()
+                .find(|node| path_b.contains(node))
+                .unwrap();
+            assert_eq!(view.lca(a, b), expected);
+        }
+    }
+}
+
+#[test]
+fn cluster_test_subtree_and_prune() {
+    // ((0, 1)5, (2, (3, 4)6)7)8
+    let linkage = [
+        [0.0, 1.0, 0.1, 2.0],
+        [3.0, 4.0, 0.2, 2.0],
+        [2.0, 6.0, 0.5, 3.0],
+        [5.0, 7.0, 0.9, 5.0],
+    ];
+    let res = ClusterHierarchy::from_linkage_matrix(&linkage).unwrap();
+
+    let sub = res.subtree(7).unwrap();
+    assert_eq!(sub.leaf_size(), 3);
+    assert_eq!(
+        sub.linkage_matrix(),
+        vec![[1.0, 2.0, 0.2, 2.0], [0.0, 3.0, 0.5, 3.0]]
+    );
+    assert_eq!(sub.labels().unwrap(), &["2", "3", "4"]);
+    assert!(res.subtree(3).is_err());
+    assert!(res.subtree(9).is_err());
+
+    let pruned = res.prune(&[3]).unwrap();
+    assert_eq!(
+        pruned.linkage_matrix(),
+        vec![
+            [0.0, 1.0, 0.1, 2.0],
+            [2.0, 3.0, 0.5, 2.0],
+            [4.0, 5.0, 0.9, 4.0]
+        ]
+    );
+    assert_eq!(pruned.labels().unwrap(), &["0", "1", "2", "4"]);
+    assert!(res.prune(&[0, 1, 2, 3]).is_err());
+    assert!(res.prune(&[5]).is_err());
+
+    // pruning keeps the cophenetic heights and labels of the remaining leaves
+    let labels: Vec<String> = (0..30).map(|i| format!("well{}", i)).collect();
+    let mut res = create_hierarchy(
+        &rand_matrix(30, 8),
+        Metric::Distance,
+        LinkageMethod::Average,
+    )
+    .unwrap();
+    res.set_labels(labels.clone()).unwrap();
+    let controls = [0, 4, 5, 17, 29];
+    let pruned = res.prune(&controls).unwrap();
+    let kept: Vec<usize> = (0..30).filter(|i| !controls.contains(i)).collect();
+    assert_eq!(pruned.leaf_size(), kept.len());
+
+    let view = res.tree_view();
+    let pruned_view = pruned.tree_view();
+    for (a, &orig_a) in kept.iter().enumerate() {
+        assert_eq!(pruned.labels().unwrap()[a], labels[orig_a]);
+        for (b, &orig_b) in kept.iter().enumerate() {
+            assert_eq!(
+                pruned_view.height(pruned_view.lca(a, b)),
+                view.height(view.lca(orig_a, orig_b))
+            );
+        }
+    }
+}
+
+#[test]
+fn cluster_test_tree_comparison() {
+    // ((0, 1), (2, (3, 4))) against ((0, 2), (1, (3, 4)))
+    let first = ClusterHierarchy::from_linkage_matrix(&[
+        [0.0, 1.0, 0.1, 2.0],
+        [3.0, 4.0, 0.2, 2.0],
+        [2.0, 6.0, 0.5, 3.0],
+        [5.0, 7.0, 0.9, 5.0],
+    ])
+    .unwrap();
+    let second = ClusterHierarchy::from_linkage_matrix(&[
+        [0.0, 2.0, 0.1, 2.0],
+        [3.0, 4.0, 0.2, 2.0],
+        [1.0, 6.0, 0.5, 3.0],
+        [5.0, 7.0, 0.9, 5.0],
+    ])
+    .unwrap();
+
+    assert_eq!(robinson_foulds(&first, &second).unwrap(), 4);
+    assert!((fowlkes_mallows(&first, &second, 2).unwrap() - 0.25).abs() < 1e-12);
+    assert!((fowlkes_mallows(&first, &second, 1).unwrap() - 1.0).abs() < 1e-12);
+    assert!(fowlkes_mallows(&first, &second, 5).is_err());
+    assert!((cophenetic_correlation(&first, &second).unwrap() + 0.042345276872964).abs() < 1e-9);
+    assert!((bakers_gamma(&first, &second).unwrap() + 0.151162790697674).abs() < 1e-9);
+
+    // the same tree with shuffled leaf ids compares as identical when matched by label
+    let mut res = create_hierarchy(
+        &rand_matrix(25, 6),
+        Metric::Distance,
+        LinkageMethod::Average,
+    )
+    .unwrap();
+    let labels: Vec<String> = (0..25).map(|i| format!("cpd{}", i)).collect();
+    res.set_labels(labels.clone()).unwrap();
+    let shuffle: Vec<usize> = (0..25).map(|i| (i * 7) % 25).collect();
+    let linkage: Vec<[f64; 4]> = res
+        .linkage_matrix()
+        .iter()
+        .map(|row| {
+            let id = |x: f64| match (x as usize) < 25 {
+                true => shuffle[x as usize] as f64,
+                false => x,
+            };
+            [id(row[0]), id(row[1]), row[2], row[3]]
+        })
+        .collect();
+    let mut shuffled = ClusterHierarchy::from_linkage_matrix(&linkage).unwrap();
+    let mut shuffled_labels = vec![String::new(); 25];
+    for (leaf, &new_id) in shuffle.iter().enumerate() {
+        shuffled_labels[new_id] = labels[leaf].clone();
+    }
+    shuffled.set_labels(shuffled_labels).unwrap();
+
+    assert_eq!(robinson_foulds(&res, &shuffled).unwrap(), 0);
+    assert!((bakers_gamma(&res, &shuffled).unwrap() - 1.0).abs() < 1e-9);
+    assert!((cophenetic_correlation(&res, &shuffled).unwrap() - 1.0).abs() < 1e-9);
+    let curve = fowlkes_mallows_curve(&res, &shuffled).unwrap();
+    assert_eq!(curve.len(), 23);
+    assert!(curve.iter().all(|&(_, b)| (b - 1.0).abs() < 1e-9));
+
+    // without labels the leaves are matched by id, so the shuffle shows up
+    let other = ClusterHierarchy::from_linkage_matrix(&linkage).unwrap();
+    assert!(robinson_foulds(&first, &other).is_err());
+    let unlabeled = ClusterHierarchy::from_linkage_matrix(&res.linkage_matrix()).unwrap();
+    assert!(robinson_foulds(&unlabeled, &other).unwrap() > 0);
+
+    // degenerate inputs have no correlation
+    let pair = ClusterHierarchy::from_linkage_matrix(&[[0.0, 1.0, 0.5, 2.0]]).unwrap();
+    assert!(bakers_gamma(&pair, &pair).is_err());
+    assert!(cophenetic_correlation(&pair, &pair).is_err());
+    let flat = ClusterHierarchy::from_linkage_matrix(&[[0.0, 1.0, 0.5, 2.0], [2.0, 3.0, 0.5, 3.0]])
+        .unwrap();
+    let nested =
+        ClusterHierarchy::from_linkage_matrix(&[[0.0, 1.0, 0.1, 2.0], [2.0, 3.0, 0.5, 3.0]])
+            .unwrap();
+    assert!(cophenetic_correlation(&flat, &nested).is_err());
+    assert!(bakers_gamma(&flat, &nested).is_ok());
+}
@@ -0,0 +1,834 @@
+use cp_hierarchical_clustering::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+fn rand_matrix(m: usize, n: usize) -> Vec<Vec<f64>> {
+    let mut rng = rand::rng();
+    (0..m)
+        .map(|_| (0..n).map(|_| rng.random::<f64>()).collect())
+        .collect()
+}
+
+fn tmp_path(fname: &str) -> String {
+    std::env::temp_dir()
+        .join(format!("cp_hc_{}_{}", std::process::id(), fname))
+        .to_string_lossy()
+        .to_string()
+}
+
+#[test]
+fn linkage_matrix_test() {
+    let matrix = rand_matrix(12, 100);
+    let res = create_hierarchy(&matrix, Metric::Pearson, LinkageMethod::Average).unwrap();
+
+    let linkage = res.linkage_matrix();
+    assert_eq!(linkage.len(), 11);
+    assert_eq!(linkage.last().unwrap()[3], 12.0);
+
+    for (i, row) in linkage.iter().enumerate() {
+        // children always refer to leaves or earlier rows
+        assert!(row[0] < (12 + i) as f64 && row[1] < (12 + i) as f64);
+        let child_size = |id: f64| {
+            if id < 12.0 {
+                1.0
+            } else {
+                linkage[id as usize - 12][3]
+            }
+        };
+        assert_eq!(row[3], child_size(row[0]) + child_size(row[1]));
+    }
+
+    let npy = tmp_path("linkage.npy");
+    res.write_linkage_npy(&npy).unwrap();
+    let bytes = std::fs::read(&npy).unwrap();
+    assert_eq!(&bytes[..6], b"\x93NUMPY");
+    let header_len = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+    assert_eq!((10 + header_len) % 64, 0);
+    assert_eq!(bytes.len(), 10 + header_len + 11 * 4 * 8);
+
+    let csv = tmp_path("linkage.csv");
+    res.write_linkage_csv(&csv).unwrap();
+    let csv_str = std::fs::read_to_string(&csv).unwrap();
+    assert_eq!(csv_str.lines().count(), 12);
+
+    let json = tmp_path("linkage.json");
+    res.write_linkage_json(&json).unwrap();
+    let parsed: Vec<[f64; 4]> =
+        serde_json::from_str(&std::fs::read_to_string(&json).unwrap()).unwrap();
+    for (a, b) in parsed.iter().zip(linkage.iter()) {
+        assert!(a.iter().zip(b.iter()).all(|(x, y)| (x - y).abs() < 1e-12));
+    }
+
+    for fname in [npy, csv, json] {
+        _ = std::fs::remove_file(fname);
+    }
+}
+
+#[test]
+fn linkage_matrix_import_test() {
+    let matrix = rand_matrix(15, 100);
+    let res = create_hierarchy(&matrix, Metric::Pearson, LinkageMethod::Complete).unwrap();
+
+    let npy = tmp_path("import.npy");
+    res.write_linkage_npy(&npy).unwrap();
+    let from_npy = ClusterHierarchy::load_linkage_npy(&npy).unwrap();
+    assert_eq!(from_npy.leaf_ordering(), res.leaf_ordering());
+    assert_eq!(from_npy.linkage_matrix(), res.linkage_matrix());
+
+    let csv = tmp_path("import.csv");
+    res.write_linkage_csv(&csv).unwrap();
+    let from_csv = ClusterHierarchy::load_linkage_csv(&csv).unwrap();
+    assert_eq!(from_csv.leaf_ordering(), res.leaf_ordering());
+    assert_eq!(from_csv.leaf_size(), 15);
+
+    for fname in [npy, csv] {
+        _ = std::fs::remove_file(fname);
+    }
+}
+
+#[test]
+fn linkage_matrix_validation_test() {
+    let valid = [
+        [0.0, 1.0, 0.1, 2.0],
+        [2.0, 3.0, 0.2, 2.0],
+        [4.0, 5.0, 0.5, 4.0],
+    ];
+    assert!(ClusterHierarchy::from_linkage_matrix(&valid).is_ok());
+
+    // id refers to a cluster that does not exist yet
+    let future_id = [
+        [0.0, 5.0, 0.1, 2.0],
+        [2.0, 3.0, 0.2, 2.0],
+        [1.0, 4.0, 0.5, 4.0],
+    ];
+    assert!(ClusterHierarchy::from_linkage_matrix(&future_id).is_err());
+
+    // leaf 0 merged twice
+    let merged_twice = [
+        [0.0, 1.0, 0.1, 2.0],
+        [0.0, 3.0, 0.2, 2.0],
+        [4.0, 5.0, 0.5, 4.0],
+    ];
+    assert!(ClusterHierarchy::from_linkage_matrix(&merged_twice).is_err());
+
+    // heights decrease
+    let non_monotonic = [
+        [0.0, 1.0, 0.3, 2.0],
+        [2.0, 3.0, 0.2, 2.0],
+        [4.0, 5.0, 0.5, 4.0],
+    ];
+    assert!(ClusterHierarchy::from_linkage_matrix(&non_monotonic).is_err());
+
+    // wrong member count
+    let bad_count = [
+        [0.0, 1.0, 0.1, 2.0],
+        [2.0, 3.0, 0.2, 2.0],
+        [4.0, 5.0, 0.5, 3.0],
+    ];
+    assert!(ClusterHierarchy::from_linkage_matrix(&bad_count).is_err());
+}
+
+#[test]
+fn load_saved_hierarchy_test() {
+    let matrix = rand_matrix(20, 100);
+    let res = create_hierarchy(&matrix, Metric::Pearson, LinkageMethod::Average).unwrap();
+
+    let merges = tmp_path("merges.json");
+    res.simple_save(&merges).unwrap();
+    let from_merges = ClusterHierarchy::load_merges(&merges).unwrap();
+    assert_eq!(from_merges.linkage_matrix(), res.linkage_matrix());
+    assert_eq!(from_merges.leaf_ordering(), res.leaf_ordering());
+
+    let tree = tmp_path("tree.json");
+    res.write_tree(&tree).unwrap();
+    let from_tree = ClusterHierarchy::load_tree(&tree).unwrap();
+    assert_eq!(from_tree.linkage_matrix(), res.linkage_matrix());
+    assert_eq!(from_tree.to_string().unwrap(), res.to_string().unwrap());
+
+    // an empty merge list only fits a hierarchy of at most one leaf
+    let empty = r#"{"merges":[],"original_n":5}"#;
+    let err = serde_json::from_str::<ClusterHierarchy>(empty).unwrap_err();
+    assert!(err.to_string().contains("0 merges can't join 5 leaves"));
+    let single: ClusterHierarchy = serde_json::from_str(r#"{"merges":[],"original_n":1}"#).unwrap();
+    assert_eq!(single.leaf_size(), 1);
+
+    for fname in [merges, tree] {
+        _ = std::fs::remove_file(fname);
+    }
+}
+
+#[test]
+fn newick_export_test() {
+    let linkage = [
+        [0.0, 1.0, 0.25, 2.0],
+        [2.0, 3.0, 0.5, 2.0],
+        [4.0, 5.0, 1.0, 4.0],
+    ];
+    let res = ClusterHierarchy::from_linkage_matrix(&linkage).unwrap();
+
+    assert_eq!(
+        res.to_newick(None, false).unwrap(),
+        "((0:0.25,1:0.25):0.75,(2:0.5,3:0.5):0.5);"
+    );
+
+    let labels: Vec<String> = ["a b", "c_d", "e'f", "g"]
+        .iter()
+        .map(|l| l.to_string())
+        .collect();
+    assert_eq!(
+        res.to_newick(Some(&labels), true).unwrap(),
+        "(('a b':0.25,'c_d':0.25)N4:0.75,('e''f':0.5,g:0.5)N5:0.5)N6;"
+    );
+    assert!(res.to_newick(Some(&labels[1..]), false).is_err());
+}
+
+#[test]
+fn newick_import_test() {
+    let matrix = rand_matrix(25, 100);
+    let res = create_hierarchy(&matrix, Metric::Pearson, LinkageMethod::Average).unwrap();
+    let labels: Vec<String> = (0..25).map(|i| format!("cpd_{} 'x'", i)).collect();
+
+    let newick = res.to_newick(Some(&labels), true).unwrap();
+    let parsed = ClusterHierarchy::from_newick(&newick, Some(&labels)).unwrap();
+    assert_eq!(parsed.labels().unwrap(), labels);
+    assert_eq!(parsed.leaf_ordering(), res.leaf_ordering());
+    for (a, b) in parsed.linkage_matrix().iter().zip(res.linkage_matrix()) {
+        assert!((a[2] - b[2]).abs() < 1e-9);
+    }
+
+    // leaves are numbered in order of appearance without a label list
+    let tree =
+        ClusterHierarchy::from_newick("((A:1,B:1)ab:2,[comment] (C : 2.5,'D E':2.5):0.5);", None)
+            .unwrap();
+    assert_eq!(tree.labels().unwrap(), vec!["A", "B", "C", "D E"]);
+    assert_eq!(tree.leaf_ordering(), vec![0, 1, 2, 3]);
+    assert_eq!(
+        tree.linkage_matrix(),
+        vec![
+            [0.0, 1.0, 1.0, 2.0],
+            [2.0, 3.0, 2.5, 2.0],
+            [4.0, 5.0, 3.0, 4.0]
+        ]
+    );
+
+    // unquoted underscores are spaces, quoted labels keep them
+    let underscored = ClusterHierarchy::from_newick("((A_b:1,'c_d':1):1,E:2);", None).unwrap();
+    assert_eq!(underscored.labels().unwrap(), vec!["A b", "c_d", "E"]);
+    let spaced: Vec<String> = ["well A1", "c_d", "E"]
+        .iter()
+        .map(|l| l.to_string())
+        .collect();
+    let newick = underscored.to_newick(Some(&spaced), true).unwrap();
+    let round_trip = ClusterHierarchy::from_newick(&newick, None).unwrap();
+    assert_eq!(round_trip.labels().unwrap(), spaced);
+
+    // not ultrametric
+    assert!(ClusterHierarchy::from_newick("((A:1,B:2):1,C:2);", None).is_err());
+    // polytomy
+    assert!(ClusterHierarchy::from_newick("(A:1,B:1,C:1);", None).is_err());
+    // unbalanced
+    assert!(ClusterHierarchy::from_newick("((A:1,B:1):1,C:2;", None).is_err());
+}
+
+fn transpose(mat: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    (0..mat[0].len())
+        .map(|c| mat.iter().map(|row| row[c]).collect())
+        .collect()
+}
+
+#[test]
+fn treeview_export_test() {
+    let matrix = rand_matrix(10, 6);
+    let rows = create_hierarchy(&matrix, Metric::Pearson, LinkageMethod::Average).unwrap();
+    let cols =
+        create_hierarchy(&transpose(&matrix), Metric::Pearson, LinkageMethod::Average).unwrap();
+    let col_labels: Vec<String> = (0..6).map(|i| format!("feature {}", i)).collect();
+
+    let prefix = tmp_path("treeview");
+    write_treeview(
+        &prefix,
+        &matrix,
+        &rows,
+        Some(&cols),
+        None,
+        Some(&col_labels),
+    )
+    .unwrap();
+
+    let cdt = std::fs::read_to_string(format!("{}.cdt", prefix)).unwrap();
+    let lines: Vec<&str> = cdt.lines().collect();
+    assert_eq!(lines.len(), 3 + 10);
+    assert!(lines[0].starts_with("GID\tUNIQID\tNAME\tGWEIGHT\tfeature "));
+    assert!(lines[1].starts_with("AID\t\t\t\tARRY"));
+    assert!(lines[2].starts_with("EWEIGHT"));
+
+    let first_row = rows.leaf_ordering()[0];
+    assert!(lines[3].starts_with(&format!("GENE{}X\t", first_row)));
+    assert!(lines.iter().all(|line| line.split('\t').count() == 4 + 6));
+
+    let gtr = std::fs::read_to_string(format!("{}.gtr", prefix)).unwrap();
+    assert_eq!(gtr.lines().count(), 9);
+    assert!(gtr.lines().last().unwrap().starts_with("NODE9X\t"));
+    let atr = std::fs::read_to_string(format!("{}.atr", prefix)).unwrap();
+    assert_eq!(atr.lines().count(), 5);
+    assert!(atr.contains("ARRY"));
+
+    for ext in ["cdt", "gtr", "atr"] {
+        _ = std::fs::remove_file(format!("{}.{}", prefix, ext));
+    }
+}
+
+#[test]
+fn labeled_hierarchy_test() {
+    let matrix = rand_matrix(10, 50);
+    let labels: Vec<String> = (0..10).map(|i| format!("well, {}", i)).collect();
+    let res = create_labeled_hierarchy(
+        &matrix,
+        Metric::Pearson,
+        LinkageMethod::Average,
+        labels.clone(),
+    )
+    .unwrap();
+
+    assert_eq!(res.labels().unwrap(), labels);
+    let expected: Vec<String> = res
+        .leaf_ordering()
+        .iter()
+        .map(|&i| labels[i].clone())
+        .collect();
+    assert_eq!(res.leaf_label_ordering(), expected);
+    assert!(res
+        .to_json_tree()
+        .unwrap()
+        .contains("\"label\": \"well, 3\""));
+
+    let merges = tmp_path("labeled_merges.json");
+    res.simple_save(&merges).unwrap();
+    assert_eq!(
+        ClusterHierarchy::load_merges(&merges)
+            .unwrap()
+            .labels()
+            .unwrap(),
+        labels
+    );
+
+    let tree = tmp_path("labeled_tree.json");
+    res.write_tree(&tree).unwrap();
+    assert_eq!(
+        ClusterHierarchy::load_tree(&tree)
+            .unwrap()
+            .labels()
+            .unwrap(),
+        labels
+    );
+
+    let order = tmp_path("labeled_order.csv");
+    res.write_leaf_order(&order).unwrap();
+    let order_str = std::fs::read_to_string(&order).unwrap();
+    assert_eq!(order_str.lines().count(), 11);
+    assert!(order_str.contains(&format!(",\"{}\"", expected[0])));
+
+    let reordered = res
+        .reorder_by_weights(&[1.0; 10], WeightAggregation::Sum)
+        .unwrap();
+    assert_eq!(reordered.labels().unwrap(), labels);
+
+    for fname in [merges, tree, order] {
+        _ = std::fs::remove_file(fname);
+    }
+}
+
+#[test]
+fn dendrogram_svg_test() {
+    let matrix = rand_matrix(10, 40);
+    let mut res = create_hierarchy(&matrix, Metric::Pearson, LinkageMethod::Average).unwrap();
+    res.set_labels((0..10).map(|i| format!("well<{}>", i)).collect())
+        .unwrap();
+
+    for orientation in [
+        Orientation::Top,
+        Orientation::Bottom,
+        Orientation::Left,
+        Orientation::Right,
+    ] {
+        let style = DendrogramStyle {
+            orientation,
+            ..Default::default()
+        };
+        let svg = res.dendrogram_svg(&style);
+        assert!(svg.starts_with("<svg") && svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches("<path").count(), 9);
+        assert!(svg.contains("well&lt;3&gt;"));
+        assert!(!svg.contains("well<3>"));
+    }
+
+    // a threshold of zero draws every link in the above threshold color
+    let style = DendrogramStyle {
+        color_threshold: Some(0.0),
+        show_axis: false,
+        show_labels: false,
+        ..Default::default()
+    };
+    let svg = res.dendrogram_svg(&style);
+    assert_eq!(svg.matches("stroke=\"#1f77b4\"").count(), 9);
+    assert!(!svg.contains("<text"));
+
+    // above the root everything is one cluster with the first palette color
+    let style = DendrogramStyle {
+        color_threshold: Some(f64::MAX),
+        ..Default::default()
+    };
+    let svg = res.dendrogram_svg(&style);
+    assert_eq!(svg.matches("stroke=\"#ff7f0e\"").count(), 9);
+
+    let fname = tmp_path("dendrogram.svg");
+    res.write_dendrogram_svg(&fname, &DendrogramStyle::default())
+        .unwrap();
+    assert!(std::fs::metadata(&fname).unwrap().len() > 0);
+    _ = std::fs::remove_file(fname);
+}
+
+#[test]
+fn heatmap_render_test() {
+    let mut matrix = rand_matrix(12, 8);
+    for row in matrix.iter_mut() {
+        for val in row.iter_mut() {
+            *val = *val * 4.0 - 2.0;
+        }
+    }
+
+    let mut heatmap = create_biclustered_heatmap(
+        &matrix,
+        Metric::Pearson,
+        LinkageMethod::Average,
+        Metric::Pearson,
+        LinkageMethod::Complete,
+    )
+    .unwrap();
+    heatmap
+        .set_labels(
+            Some((0..12).map(|i| format!("W{:02}", i)).collect()),
+            Some((0..8).map(|i| format!("f{}", i)).collect()),
+        )
+        .unwrap();
+
+    let style = HeatmapStyle {
+        colormap: ColorMap {
+            vmin: Some(-1.0),
+            vmax: Some(1.0),
+            ..Default::default()
+        },
+        row_annotations: vec![Annotation {
+            name: "treatment".to_string(),
+            values: (0..12)
+                .map(|i| if i % 2 == 0 { "DMSO" } else { "drug" }.to_string())
+                .collect(),
+        }],
+        ..Default::default()
+    };
+
+    let svg = heatmap.to_svg(&style).unwrap();
+    // cells and one annotation bar per row
+    assert_eq!(svg.matches("<rect x=").count(), 12 * 8 + 12 + 2 + 1);
+    // clipped extremes take the end colors
+    assert!(svg.contains("fill=\"#3b4cc0\""));
+    assert!(svg.contains("fill=\"#b40426\""));
+    // both dendrograms
+    assert_eq!(svg.matches("<path").count(), 11 + 7);
+    assert!(svg.contains(">W03<") && svg.contains(">f7<") && svg.contains(">drug<"));
+
+    // the direct api matches the struct method
+    let direct = heatmap_svg(
+        &matrix,
+        heatmap.row_hierarchy(),
+        Some(heatmap.col_hierarchy()),
+        &style,
+    )
+    .unwrap();
+    assert_eq!(direct, svg);
+
+    // missing values use the nan color
+    let mut missing = matrix.clone();
+    missing[3][2] = f64::NAN;
+    let svg = heatmap_svg(
+        &missing,
+        heatmap.row_hierarchy(),
+        Some(heatmap.col_hierarchy()),
+        &style,
+    )
+    .unwrap();
+    assert!(svg.contains("fill=\"#c8c8c8\""));
+
+    let bad_style = HeatmapStyle {
+        col_annotations: vec![Annotation {
+            name: "plate".to_string(),
+            values: vec!["P1".to_string(); 3],
+        }],
+        ..Default::default()
+    };
+    assert!(heatmap.to_svg(&bad_style).is_err());
+
+    #[cfg(feature = "png")]
+    {
+        let png = tmp_path("heatmap.png");
+        heatmap.write_png(&png, &style).unwrap();
+        let bytes = std::fs::read(&png).unwrap();
+        assert_eq!(&bytes[..8], b"\x89PNG\r\n\x1a\n");
+        _ = std::fs::remove_file(png);
+    }
+}
+
+#[test]
+fn html_report_test() {
+    let matrix = rand_matrix(10, 6);
+    let rows = create_labeled_hierarchy(
+        &matrix,
+        Metric::Pearson,
+        LinkageMethod::Average,
+        (0..10).map(|i| format!("cpd</script>{}", i)).collect(),
+    )
+    .unwrap();
+    let cols =
+        create_hierarchy(&transpose(&matrix), Metric::Pearson, LinkageMethod::Average).unwrap();
+
+    let options = ReportOptions {
+        title: "Plate <1>".to_string(),
+        row_metadata: vec![Annotation {
+            name: "moa".to_string(),
+            values: (0..10).map(|i| format!("moa{}", i % 3)).collect(),
+        }],
+        ..Default::default()
+    };
+    let html = html_report(&matrix, &rows, Some(&cols), &options).unwrap();
+
+    // self-contained: no external scripts or styles
+    assert!(!html.contains("src=\"http") && !html.contains("href=\"http"));
+    assert!(html.contains("<title>Plate &lt;1&gt;</title>"));
+    // labels cannot close the embedded script
+    assert_eq!(html.matches("</script>").count(), 2);
+    assert!(html.contains("cpd\\u003c/script>3"));
+
+    let start = html.find("type=\"application/json\">").unwrap() + 24;
+    let end = start + html[start..].find("</script>").unwrap();
+    let payload: serde_json::Value = serde_json::from_str(&html[start..end]).unwrap();
+    assert_eq!(payload["rows"]["linkage"].as_array().unwrap().len(), 9);
+    assert_eq!(payload["cols"]["labels"].as_array().unwrap().len(), 6);
+    assert_eq!(payload["rows"]["meta"][0]["values"][4], "moa1");
+
+    let bad = ReportOptions {
+        col_metadata: vec![Annotation {
+            name: "channel".to_string(),
+            values: vec!["DNA".to_string()],
+        }],
+        ..Default::default()
+    };
+    assert!(html_report(&matrix, &rows, Some(&cols), &bad).is_err());
+}
+
+#[test]
+fn d3_json_test() {
+    let matrix = rand_matrix(8, 30);
+    let res = create_labeled_hierarchy(
+        &matrix,
+        Metric::Pearson,
+        LinkageMethod::Average,
+        (0..8).map(|i| format!("cpd{}", i)).collect(),
+    )
+    .unwrap();
+
+    let clusters = res.flat_clusters(3).unwrap();
+    let mut options = D3Options {
+        clusters: Some(clusters.clone()),
+        ..Default::default()
+    };
+    options.metadata.insert(
+        "dose".to_string(),
+        (0..8).map(|i| serde_json::json!(i as f64 * 0.5)).collect(),
+    );
+
+    let json: serde_json::Value = serde_json::from_str(&res.to_d3_json(&options).unwrap()).unwrap();
+    assert_eq!(json["size"], 8);
+    assert_eq!(json["children"].as_array().unwrap().len(), 2);
+    assert!(json.get("cluster").is_none());
+
+    // walk the tree: leaves in leaf order with their metadata, heights decrease downwards
+    let mut leaves = Vec::new();
+    let mut stack = vec![&json];
+    while let Some(node) = stack.pop() {
+        match node.get("children") {
+            Some(children) => {
+                let children = children.as_array().unwrap();
+                for child in children.iter().rev() {
+                    assert!(child["height"].as_f64().unwrap() <= node["height"].as_f64().unwrap());
+                    stack.push(child);
+                }
+            }
+            None => leaves.push(node),
+        }
+    }
+
+    let order = res.leaf_ordering();
+    assert_eq!(leaves.len(), 8);
+    for (leaf, &id) in leaves.iter().zip(order.iter()) {
+        assert_eq!(leaf["leaf"], id);
+        assert_eq!(leaf["name"], format!("cpd{}", id));
+        assert_eq!(leaf["cluster"], clusters[id]);
+        assert_eq!(leaf["dose"], id as f64 * 0.5);
+        assert_eq!(leaf["size"], 1);
+    }
+
+    options
+        .metadata
+        .insert("name".to_string(), vec![serde_json::json!(1); 8]);
+    assert!(res.to_d3_json(&options).is_err());
+}
+
+#[test]
+fn dot_export_test() {
+    let linkage = [
+        [0.0, 1.0, 0.1, 2.0],
+        [2.0, 3.0, 0.25, 2.0],
+        [4.0, 5.0, 1.0, 4.0],
+    ];
+    let mut res = ClusterHierarchy::from_linkage_matrix(&linkage).unwrap();
+    res.set_labels((0..4).map(|i| format!("well \"{}\"", i)).collect())
+        .unwrap();
+
+    let dot = res.to_dot(&DotOptions::default()).unwrap();
+    assert!(dot.starts_with("digraph dendrogram {"));
+    assert_eq!(dot.matches("shape=box").count(), 4);
+    assert_eq!(dot.matches("shape=ellipse").count(), 3);
+    assert_eq!(dot.matches(" -> ").count(), 6);
+    assert!(dot.contains("label=\"well \\\"0\\\"\""));
+    assert!(dot.contains("label=\"N6\\nh=1.000\""));
+
+    // branch lengths are the height differences
+    assert!(dot.contains("n6 -> n4 [len=0.9]"));
+    assert!(dot.contains("n6 -> n5 [len=0.75]"));
+    assert!(dot.contains("n4 -> n0 [len=0.1]"));
+
+    // collapsing below the root leaves the root and its two children
+    let mut options = DotOptions {
+        collapse_below: Some(0.5),
+        clusters: Some(res.flat_clusters(2).unwrap()),
+        edge_labels: true,
+        ..Default::default()
+    };
+    let collapsed = res.to_dot(&options).unwrap();
+    assert_eq!(collapsed.matches(" -> ").count(), 2);
+    assert_eq!(collapsed.matches("shape=triangle").count(), 2);
+    assert!(collapsed.contains("2 leaves"));
+    assert!(collapsed.contains("label=\"0.900\""));
+    assert!(
+        collapsed.contains("fillcolor=\"#1f77b4\"") && collapsed.contains("fillcolor=\"#ff7f0e\"")
+    );
+    assert!(collapsed.contains("class=\"cluster_1\"") && !collapsed.contains("cluster="));
+
+    // zero height merges keep a positive edge length
+    let flat = ClusterHierarchy::from_linkage_matrix(&[[0.0, 1.0, 0.0, 2.0]]).unwrap();
+    let flat_dot = flat.to_dot(&DotOptions::default()).unwrap();
+    assert!(flat_dot.contains("n2 -> n0 [len=0.001]") && !flat_dot.contains("len=0]"));
+
+    options.clusters = Some(vec![1; 3]);
+    assert!(res.to_dot(&options).is_err());
+}
+
+#[test]
+fn deep_tree_test() {
+    // single linkage style chain: every merge adds one leaf to the previous cluster
+    let n = 100_000;
+    let linkage: Vec<[f64; 4]> = (0..n - 1)
+        .map(|i| {
+            let prev = if i == 0 { 0 } else { n + i - 1 };
+            [prev as f64, (i + 1) as f64, (i + 1) as f64, (i + 2) as f64]
+        })
+        .collect();
+    let res = ClusterHierarchy::from_linkage_matrix(&linkage).unwrap();
+
+    let order = res.leaf_ordering();
+    assert_eq!(order, (0..n).collect::<Vec<usize>>());
+
+    let view = res.tree_view();
+    assert_eq!(view.depth(0), n - 1);
+    assert_eq!(view.lca(0, 1), n);
+    assert_eq!(view.lca(1, n - 1), view.root());
+    assert_eq!(view.postorder().count(), 2 * n - 1);
+
+    let copy = res.clone();
+    drop(res);
+
+    let fname = tmp_path("deep_tree.json");
+    copy.write_compact_tree(&fname).unwrap();
+    let loaded = ClusterHierarchy::load_tree(&fname).unwrap();
+    assert_eq!(loaded.linkage_matrix(), copy.linkage_matrix());
+
+    let root = copy.get_raw_nodes().unwrap();
+    let root_json = serde_json::to_string(&root).unwrap();
+    assert_eq!(root_json, copy.to_compact_json_tree().unwrap());
+    // serde_json stops at 128 levels unless its own limit is lifted
+    assert!(serde_json::from_str::<DendrogramNode>(&root_json).is_err());
+    let mut deserializer = serde_json::Deserializer::from_str(&root_json);
+    deserializer.disable_recursion_limit();
+    let root_back = DendrogramNode::deserialize(&mut deserializer).unwrap();
+    assert_eq!(root_back.size, n);
+    assert!(format!("{:?}", root_back).starts_with("DendrogramNode { cid: "));
+
+    let json = serde_json::to_string(&copy).unwrap();
+    let restored: ClusterHierarchy = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.leaf_ordering(), order);
+
+    let d3 = copy.to_d3_json(&D3Options::default()).unwrap();
+    // validated by serde_json without building the nested value
+    serde_json::from_str::<&serde_json::value::RawValue>(&d3).unwrap();
+    assert_eq!(d3.matches("\"leaf\":").count(), n);
+
+    let newick = copy.to_newick(None, false).unwrap();
+    let parsed = ClusterHierarchy::from_newick(&newick, None).unwrap();
+    assert_eq!(parsed.leaf_size(), n);
+}
+
+#[test]
+fn json_tree_format_test() {
+    let matrix = rand_matrix(7, 20);
+    let res = create_labeled_hierarchy(
+        &matrix,
+        Metric::Distance,
+        LinkageMethod::Complete,
+        (0..7).map(|i| format!("\"cpd\" {}", i)).collect(),
+    )
+    .unwrap();
+
+    // same text as the derived serde output of the same struct, and it reads back
+    #[derive(Serialize, Deserialize)]
+    struct Node {
+        cid: usize,
+        distance: f64,
+        size: usize,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        label: Option<String>,
+        left: Option<Box<Node>>,
+        right: Option<Box<Node>>,
+    }
+    let compact = res.to_compact_json_tree().unwrap();
+    let derived: Node = serde_json::from_str(&compact).unwrap();
+    assert_eq!(compact, serde_json::to_string(&derived).unwrap());
+    let json = res.to_json_tree().unwrap();
+    assert_eq!(json, serde_json::to_string_pretty(&derived).unwrap());
+
+    let root = res.get_raw_nodes().unwrap();
+    assert_eq!(serde_json::to_string(&root).unwrap(), compact);
+    let root_back: DendrogramNode = serde_json::from_str(&json).unwrap();
+    assert_eq!(format!("{:?}", root_back), format!("{:?}", root));
+
+    // not tied to json text: through serde_json's `Value` serializer and back
+    let value = serde_json::to_value(&root).unwrap();
+    assert_eq!(value["left"]["cid"], root.left.as_ref().unwrap().cid);
+    let from_value: DendrogramNode = serde_json::from_value(value).unwrap();
+    assert_eq!(format!("{:?}", from_value), format!("{:?}", root));
+
+    let fname = tmp_path("json_tree_format.json");
+    res.write_tree(&fname).unwrap();
+    let loaded = ClusterHierarchy::load_tree(&fname).unwrap();
+    assert_eq!(loaded.to_json_tree().unwrap(), json);
+
+    let restored: ClusterHierarchy =
+        serde_json::from_str(&serde_json::to_string(&res).unwrap()).unwrap();
+    assert_eq!(restored.to_json_tree().unwrap(), json);
+
+    let mut bad = serde_json::to_value(&res).unwrap();
+    bad["original_n"] = serde_json::json!(3);
+    assert!(serde_json::from_value::<ClusterHierarchy>(bad).is_err());
+}
+
+#[test]
+fn tanglegram_test() {
+    let labels: Vec<String> = ["a", "b", "c", "d"].iter().map(|l| l.to_string()).collect();
+    // ((a, b), (c, d)) against ((d, c), (b, a))
+    let mut left = ClusterHierarchy::from_linkage_matrix(&[
+        [0.0, 1.0, 0.1, 2.0],
+        [2.0, 3.0, 0.2, 2.0],
+        [4.0, 5.0, 0.6, 4.0],
+    ])
+    .unwrap();
+    left.set_labels(labels.clone()).unwrap();
+    let mut right = ClusterHierarchy::from_linkage_matrix(&[
+        [3.0, 2.0, 0.3, 2.0],
+        [1.0, 0.0, 0.4, 2.0],
+        [4.0, 5.0, 0.8, 4.0],
+    ])
+    .unwrap();
+    right.set_labels(labels.clone()).unwrap();
+
+    assert_eq!(Tanglegram::new(&left, &right).unwrap().crossings(), 6);
+    let tangle = untangle(&left, &right, UntangleMethod::OneSide).unwrap();
+    assert_eq!(tangle.crossings(), 0);
+    assert_eq!(tangle.right_order(), vec![3, 2, 1, 0]);
+    assert_eq!(tangle.left_order(), vec![3, 2, 1, 0]);
+
+    // replicate plates: same compounds, noisy profiles, shuffled leaf ids
+    let n = 30;
+    let matrix = rand_matrix(n, 12);
+    let names: Vec<String> = (0..n).map(|i| format!("cpd{}", i)).collect();
+    let first = create_labeled_hierarchy(
+        &matrix,
+        Metric::Distance,
+        LinkageMethod::Average,
+        names.clone(),
+    )
+    .unwrap();
+    let shuffle: Vec<usize> = (0..n).map(|i| (i * 11) % n).collect();
+    let mut replicate = vec![vec![]; n];
+    let mut replicate_names = vec![String::new(); n];
+    for (i, &j) in shuffle.iter().enumerate() {
+        replicate[j] = matrix[i]
+            .iter()
+            .map(|x| x + 0.05 * (i as f64).sin())
+            .collect();
+        replicate_names[j] = names[i].clone();
+    }
+    let second = create_labeled_hierarchy(
+        &replicate,
+        Metric::Distance,
+        LinkageMethod::Average,
+        replicate_names,
+    )
+    .unwrap()
+    .prune(&[0])
+    .unwrap();
+
+    let start = Tanglegram::new(&first, &second).unwrap();
+    assert_eq!(start.links.len(), n - 1);
+    let one_side = untangle(&first, &second, UntangleMethod::OneSide).unwrap();
+    let two_side = untangle(&first, &second, UntangleMethod::TwoSide).unwrap();
+    assert!(one_side.crossings() <= start.crossings());
+    assert!(two_side.crossings() <= one_side.crossings());
+    assert_eq!(one_side.right_order(), second.leaf_ordering());
+
+    // rotations keep the heights and the clusters
+    assert_eq!(robinson_foulds(&two_side.left, &first).unwrap(), 0);
+    assert_eq!(robinson_foulds(&two_side.right, &second).unwrap(), 0);
+
+    let style = TanglegramStyle {
+        link_clusters: Some(3),
+        ..Default::default()
+    };
+    let svg = two_side.to_svg(&style).unwrap();
+    assert!(svg.starts_with("<svg"));
+    assert_eq!(svg.matches("<line").count(), n - 1);
+    assert_eq!(svg.matches("<text").count(), 2 * n - 1);
+    assert!(svg.contains(">cpd7</text>"));
+
+    let fname = tmp_path("tanglegram_orders.csv");
+    two_side.write_orders(&fname).unwrap();
+    let csv = std::fs::read_to_string(&fname).unwrap();
+    let rows: Vec<&str> = csv.lines().collect();
+    assert_eq!(
+        rows[0],
+        "position,left_leaf,left_label,right_leaf,right_label"
+    );
+    assert_eq!(rows.len(), n + 1);
+    assert!(rows[n].ends_with(",,"));
+
+    let unlabeled = ClusterHierarchy::from_linkage_matrix(&[[0.0, 1.0, 0.1, 2.0]]).unwrap();
+    assert!(Tanglegram::new(&unlabeled, &left).is_err());
+}
@@ -60,3 +60,46 @@ fn calc_matrix_pearson_dist_test() {
 
     println!("{:?}", matrix_to_df(&res).unwrap());
 }
+
+#[test]
+fn calc_matrix_from_array_test() {
+    let matrix = rand_matrix(50, 200);
+    let flat: Vec<f64> = matrix.iter().flatten().copied().collect();
+    let array = ndarray::Array2::from_shape_vec((50, 200), flat).unwrap();
+
+    let expected = calculate_matrix(&matrix, Metric::Pearson, true);
+    let res = calculate_matrix_from_array(array.view(), Metric::Pearson, true);
+    assert_eq!(res.dim(), (50, 50));
+    for i in 0..50 {
+        for j in 0..50 {
+            assert_eq!(res[[i, j]], expected[i][j]);
+        }
+    }
+
+    // column major input takes the copying path
+    let fortran = array.t().to_owned().reversed_axes();
+    let res_f = calculate_matrix_from_array(fortran.view(), Metric::Pearson, true);
+    assert_eq!(res_f, res);
+
+    let hierarchy =
+        create_hierarchy_from_array(array.view(), Metric::Pearson, LinkageMethod::Average).unwrap();
+    let expected_hierarchy =
+        create_hierarchy(&matrix, Metric::Pearson, LinkageMethod::Average).unwrap();
+    assert_eq!(
+        hierarchy.leaf_ordering(),
+        expected_hierarchy.leaf_ordering()
+    );
+
+    // the distances are read straight from strided views too
+    let from_transposed =
+        hierarchical_clustering_from_array(res.t(), LinkageMethod::Average).unwrap();
+    assert_eq!(from_transposed.linkage_matrix(), hierarchy.linkage_matrix());
+
+    assert!(hierarchical_clustering_from_array(array.view(), LinkageMethod::Single).is_err());
+
+    // non-square and ragged matrices are rejected instead of shifting the condensed indexing
+    let wide = vec![vec![0.0, 1.0, 5.0, 2.0]; 3];
+    assert!(hierarchical_clustering(&wide, LinkageMethod::Single).is_err());
+    let ragged = vec![vec![0.0, 1.0, 2.0], vec![1.0, 0.0], vec![2.0, 3.0, 0.0]];
+    assert!(hierarchical_clustering(&ragged, LinkageMethod::Single).is_err());
+}